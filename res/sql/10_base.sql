@@ -1,26 +1,51 @@
+DROP TABLE IF EXISTS artist CASCADE;
 DROP TABLE IF EXISTS watch CASCADE;
 DROP TABLE IF EXISTS artist_release CASCADE;
 DROP TABLE IF EXISTS artist_release_watch_alerted CASCADE;
+DROP TABLE IF EXISTS guild_settings CASCADE;
+DROP TABLE IF EXISTS user_subscription CASCADE;
+DROP TABLE IF EXISTS artist_release_subscription_alerted CASCADE;
 DROP VIEW IF EXISTS vw_all_watched_artists CASCADE;
 DROP VIEW IF EXISTS vw_unalerted_watches CASCADE;
+DROP VIEW IF EXISTS vw_unalerted_subscriptions CASCADE;
+
+CREATE TABLE artist
+(
+    id_artist          VARCHAR(32)  NOT NULL,
+    time_updated       TIMESTAMPTZ  NOT NULL,
+
+    name               TEXT         NOT NULL,
+    image_url          VARCHAR(128),
+    genres             TEXT[]       NOT NULL,
+    followers          INT          NOT NULL,
+    popularity         INT          NOT NULL,
+    appears_on_scanned BOOL         NOT NULL DEFAULT FALSE,
+
+    CONSTRAINT artist_pk PRIMARY KEY (id_artist)
+);
 
 CREATE TABLE watch
 (
-    id                INT         NOT NULL GENERATED ALWAYS AS IDENTITY,
+    id                INT           NOT NULL GENERATED ALWAYS AS IDENTITY,
 
-    time_created      TIMESTAMPTZ NOT NULL,
-    id_created_by     VARCHAR(64) NOT NULL,
-    id_server         VARCHAR(64) NOT NULL,
-    id_alert_channel  VARCHAR(64) NOT NULL,
-    market            CHAR(2)     NOT NULL,
+    time_created      TIMESTAMPTZ   NOT NULL,
+    id_created_by     VARCHAR(64)   NOT NULL,
+    id_server         VARCHAR(64)   NOT NULL,
+    id_alert_channel  VARCHAR(64)   NOT NULL,
+    markets           CHAR(2)[]     NOT NULL,
+    album_groups      VARCHAR(16)[] NOT NULL,
 
-    id_artist         VARCHAR(32) NOT NULL,
+    id_artist         VARCHAR(32)   NOT NULL,
 
-    has_initialized   BOOL        NOT NULL,
-    time_last_scanned TIMESTAMPTZ NOT NULL,
+    has_initialized   BOOL          NOT NULL,
+    time_last_scanned TIMESTAMPTZ   NOT NULL,
+    pause_mode        VARCHAR(8),
+    id_mention_role   VARCHAR(64),
+    disabled_reason   TEXT,
 
     CONSTRAINT watch_pk PRIMARY KEY (id),
-    CONSTRAINT watch_server_artist_uniq UNIQUE (id_server, id_artist)
+    CONSTRAINT watch_server_artist_uniq UNIQUE (id_server, id_artist),
+    CONSTRAINT watch_id_artist_fk FOREIGN KEY (id_artist) REFERENCES artist (id_artist)
 );
 
 CREATE TABLE artist_release
@@ -32,6 +57,7 @@ CREATE TABLE artist_release
     artist_ids             VARCHAR(32)[] NOT NULL,
     artist_names           TEXT[]        NOT NULL,
     album_type             VARCHAR(16)   NOT NULL,
+    album_group            VARCHAR(16)   NOT NULL,
     available_markets      CHAR(2)[]     NOT NULL,
     href                   VARCHAR(128)  NOT NULL,
     image_url              VARCHAR(128)  NOT NULL,
@@ -39,37 +65,132 @@ CREATE TABLE artist_release
     release_date           VARCHAR(16)   NOT NULL,
     release_date_precision VARCHAR(8)    NOT NULL,
 
-    CONSTRAINT artist_release_pk PRIMARY KEY (id_release)
+    CONSTRAINT artist_release_pk PRIMARY KEY (id_release, id_artist)
 );
 
 CREATE TABLE artist_release_watch_alerted
 (
-    id_release VARCHAR(32) NOT NULL,
-    id_watch   INT         NOT NULL,
+    id_release        VARCHAR(32) NOT NULL,
+    id_watch          INT         NOT NULL,
+
+    outcome           VARCHAR(8)  NOT NULL DEFAULT 'sent',
+    id_channel        VARCHAR(64),
+    id_message        VARCHAR(64),
+    time_sent         TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    attempts          INT         NOT NULL DEFAULT 0,
+    last_error        TEXT,
+    time_next_attempt TIMESTAMPTZ,
 
     CONSTRAINT artist_release_watch_alerted_pk PRIMARY KEY (id_release, id_watch),
-    CONSTRAINT artist_release_watch_alerted_id_release_fk FOREIGN KEY (id_release) REFERENCES artist_release (id_release),
     CONSTRAINT artist_release_watch_alerted_id_watch_fk FOREIGN KEY (id_watch) REFERENCES watch (id)
 );
 
+CREATE TABLE guild_settings
+(
+    id_server             VARCHAR(64)   NOT NULL,
+
+    pause_mode            VARCHAR(8),
+    manager_role_ids      VARCHAR(64)[] NOT NULL DEFAULT '{}',
+    manager_permissions   BIGINT        NOT NULL DEFAULT 0,
+    default_alert_channel VARCHAR(64),
+    default_markets       CHAR(2)[],
+    default_album_groups  VARCHAR(16)[],
+    embed_style           VARCHAR(16)   NOT NULL DEFAULT 'full',
+    link_channel_ids      VARCHAR(64)[] NOT NULL DEFAULT '{}',
+
+    CONSTRAINT guild_settings_pk PRIMARY KEY (id_server)
+);
+
+CREATE TABLE user_subscription
+(
+    id              INT           NOT NULL GENERATED ALWAYS AS IDENTITY,
+
+    time_created    TIMESTAMPTZ   NOT NULL,
+    id_user         VARCHAR(64)   NOT NULL,
+    markets         CHAR(2)[]     NOT NULL,
+    album_groups    VARCHAR(16)[] NOT NULL,
+
+    id_artist       VARCHAR(32)   NOT NULL,
+
+    has_initialized BOOL          NOT NULL,
+    dm_blocked      BOOL          NOT NULL DEFAULT FALSE,
+
+    CONSTRAINT user_subscription_pk PRIMARY KEY (id),
+    CONSTRAINT user_subscription_user_artist_uniq UNIQUE (id_user, id_artist),
+    CONSTRAINT user_subscription_id_artist_fk FOREIGN KEY (id_artist) REFERENCES artist (id_artist)
+);
+
+CREATE TABLE artist_release_subscription_alerted
+(
+    id_release      VARCHAR(32) NOT NULL,
+    id_subscription INT         NOT NULL,
+
+    CONSTRAINT artist_release_subscription_alerted_pk PRIMARY KEY (id_release, id_subscription),
+    CONSTRAINT artist_release_subscription_alerted_id_subscription_fk FOREIGN KEY (id_subscription) REFERENCES user_subscription (id)
+);
+
 CREATE VIEW vw_all_watched_artists AS
-SELECT DISTINCT id_artist
-FROM watch;
+SELECT id_artist,
+       BOOL_OR(scan_appears_on) AS scan_appears_on
+FROM (SELECT id_artist, 'appears_on' = ANY (album_groups) AS scan_appears_on
+      FROM watch
+      UNION ALL
+      SELECT id_artist, 'appears_on' = ANY (album_groups) AS scan_appears_on
+      FROM user_subscription) s
+GROUP BY id_artist;
 
 CREATE VIEW vw_unalerted_watches AS
 SELECT w.id AS id_watch,
        w.has_initialized,
        w.id_server,
        w.id_alert_channel,
-       w.market,
+       w.markets,
+       w.id_mention_role,
+       COALESCE(w.pause_mode, gs.pause_mode) AS pause_mode,
+       COALESCE(gs.embed_style, 'full') AS embed_style,
+       a.name      AS artist_name,
+       a.image_url AS artist_image_url,
        ar.id_release,
+       ar.id_artist,
        ar.artist_names,
        ar.album_type,
+       ar.album_group,
+       ar.album_group = ANY (w.album_groups) AS matches_filter,
        ar.href,
        ar.image_url,
        ar.name,
-       ar.release_date
+       ar.release_date,
+       COALESCE(arwa.attempts, 0) AS attempts
 FROM watch w
-INNER JOIN artist_release ar ON w.id_artist = ar.id_artist AND w.market = ANY (ar.available_markets)
+INNER JOIN artist a ON w.id_artist = a.id_artist
+INNER JOIN artist_release ar ON w.id_artist = ar.id_artist AND w.markets && ar.available_markets
 LEFT JOIN artist_release_watch_alerted arwa ON w.id = arwa.id_watch AND ar.id_release = arwa.id_release
-WHERE arwa.id_watch IS NULL;
+LEFT JOIN guild_settings gs ON w.id_server = gs.id_server
+WHERE (arwa.id_watch IS NULL OR (arwa.outcome = 'failed' AND arwa.time_next_attempt <= NOW()))
+  AND w.disabled_reason IS NULL
+  AND (ar.album_group <> 'appears_on' OR a.appears_on_scanned)
+  AND COALESCE(w.pause_mode, gs.pause_mode) IS DISTINCT FROM 'queue';
+
+CREATE VIEW vw_unalerted_subscriptions AS
+SELECT us.id AS id_subscription,
+       us.has_initialized,
+       us.id_user,
+       us.dm_blocked,
+       a.name      AS artist_name,
+       a.image_url AS artist_image_url,
+       ar.id_release,
+       ar.id_artist,
+       ar.artist_names,
+       ar.album_type,
+       ar.album_group,
+       ar.album_group = ANY (us.album_groups) AS matches_filter,
+       ar.href,
+       ar.image_url,
+       ar.name,
+       ar.release_date
+FROM user_subscription us
+INNER JOIN artist a ON us.id_artist = a.id_artist
+INNER JOIN artist_release ar ON us.id_artist = ar.id_artist AND us.markets && ar.available_markets
+LEFT JOIN artist_release_subscription_alerted arsa ON us.id = arsa.id_subscription AND ar.id_release = arsa.id_release
+WHERE arsa.id_subscription IS NULL
+  AND (ar.album_group <> 'appears_on' OR a.appears_on_scanned);
@@ -0,0 +1,194 @@
+use evlog::meta;
+use serenity::builder::{CreateApplicationCommand, CreateEmbed};
+use serenity::client::Context;
+use serenity::model::id::GuildId;
+use serenity::model::interactions::application_command::{ApplicationCommandInteraction, ApplicationCommandOptionType};
+use serenity::utils::Color;
+
+use crate::commands::bulk;
+use crate::db;
+use crate::db::schema::AlertedRelease;
+use crate::handler::BotData;
+use crate::helpers::{command_opt, command_resp, permissions};
+use crate::monitor::{ALERT_DEAD, ALERT_SENT};
+use crate::runtime::get_logger;
+
+pub const ALERTS: &str = "alerts";
+
+/// The number of recent alerts `/alerts history` and `/alerts failed` show.
+const HISTORY_SIZE: i64 = 15;
+
+pub fn alerts_builder(cmd: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    cmd.name(ALERTS)
+        .description("Review the release alerts sent in this server")
+        .create_option(|sub| sub
+            .name("history")
+            .description("List this server's most recent release alerts")
+            .kind(ApplicationCommandOptionType::SubCommand))
+        .create_option(|sub| sub
+            .name("failed")
+            .description("List alerts that are waiting to be retried or were given up on")
+            .kind(ApplicationCommandOptionType::SubCommand))
+        .create_option(|sub| sub
+            .name("replay")
+            .description("Retry every alert that was given up on after failing too many times")
+            .kind(ApplicationCommandOptionType::SubCommand))
+}
+
+pub async fn alerts(ctx: Context, interaction: ApplicationCommandInteraction) -> anyhow::Result<()> {
+    match command_opt::find_subcommand(&interaction) {
+        Some("history") => history(ctx, interaction).await,
+        Some("failed") => failed(ctx, interaction).await,
+        Some("replay") => replay(ctx, interaction).await,
+        _ => Ok(()),
+    }
+}
+
+async fn history(ctx: Context, interaction: ApplicationCommandInteraction) -> anyhow::Result<()> {
+    let guild_id = match interaction.guild_id {
+        None => {
+            command_resp::reply(&ctx, &interaction, |r| r.content("/alerts history can only be used in a server.")).await?;
+            return Ok(());
+        }
+        Some(v) => v,
+    };
+
+    let history = {
+        let data = ctx.data.read().await;
+        let data = data.get::<BotData>().unwrap();
+
+        db::model::list_server_alert_history(data.db_client.conn(), *guild_id.as_u64(), HISTORY_SIZE).await?
+    };
+
+    command_resp::reply(&ctx, &interaction, |r| if history.is_empty() {
+        r.content("No release alerts have been sent in this server yet.")
+    } else {
+        r.add_embed(build_history_embed(guild_id, &history))
+    }).await?;
+
+    Ok(())
+}
+
+async fn failed(ctx: Context, interaction: ApplicationCommandInteraction) -> anyhow::Result<()> {
+    let guild_id = match interaction.guild_id {
+        None => {
+            command_resp::reply(&ctx, &interaction, |r| r.content("/alerts failed can only be used in a server.")).await?;
+            return Ok(());
+        }
+        Some(v) => v,
+    };
+
+    let failed = {
+        let data = ctx.data.read().await;
+        let data = data.get::<BotData>().unwrap();
+
+        db::model::list_server_failed_alerts(data.db_client.conn(), *guild_id.as_u64(), HISTORY_SIZE).await?
+    };
+
+    command_resp::reply(&ctx, &interaction, |r| if failed.is_empty() {
+        r.content("No release alerts in this server are failing to send.")
+    } else {
+        r.add_embed(build_failed_embed(&failed))
+    }).await?;
+
+    Ok(())
+}
+
+async fn replay(ctx: Context, interaction: ApplicationCommandInteraction) -> anyhow::Result<()> {
+    command_resp::reply_deferred_ack(&ctx, &interaction).await?;
+
+    let guild_id = match permissions::authorize_watch_manager(&ctx, &interaction, "/alerts replay").await? {
+        None => return Ok(()),
+        Some(v) => v,
+    };
+
+    let data = ctx.data.read().await;
+    let data = data.get::<BotData>().unwrap();
+
+    let count = db::model::replay_server_dead_alerts(data.db_client.conn(), *guild_id.as_u64()).await?;
+
+    get_logger().info("Replayed dead-lettered alerts.", meta! {
+        "InteractionID" => interaction.id,
+        "GuildID" => guild_id,
+        "Count" => count,
+    });
+
+    command_resp::reply_deferred_result(&ctx, &interaction, if count == 0 {
+        "There are no given-up alerts to retry in this server. Alerts of disabled watches can be retried once the watch is fixed with /watch edit.".to_owned()
+    } else {
+        format!("Retrying {} {} on the next scan.", count, if count == 1 { "alert" } else { "alerts" })
+    }).await.unwrap();
+
+    Ok(())
+}
+
+fn build_history_embed(guild_id: GuildId, history: &[AlertedRelease]) -> CreateEmbed {
+    let mut e = CreateEmbed::default();
+
+    e.author(|a| {
+        a.name("Spotlit");
+        a.icon_url("https://i.imgur.com/iKUvWHR.png");
+
+        a
+    });
+
+    e.title("Recent alerts");
+    e.color(Color::from_rgb(30, 215, 96));
+
+    let lines = history.iter()
+        .map(|alert| match (alert.outcome.as_str(), alert.id_channel, alert.id_message) {
+            (ALERT_SENT, Some(channel), Some(message)) => format!(
+                "<t:{}:R> **{}** · [{}](https://discord.com/channels/{}/{}/{})",
+                alert.time_sent.timestamp(), alert.artist_name, alert.name, guild_id, channel, message,
+            ),
+            _ => format!(
+                "<t:{}:R> **{}** · {} · {}{}",
+                alert.time_sent.timestamp(), alert.artist_name, alert.name,
+                if alert.outcome == ALERT_DEAD { "gave up sending" } else { "failed to send" },
+                alert.id_channel.map_or(String::new(), |v| format!(" to <#{}>", v)),
+            ),
+        })
+        .collect::<Vec<_>>();
+
+    // Embed descriptions are limited to 4096 characters.
+    e.description(bulk::join_truncated(&lines, 4096));
+
+    e
+}
+
+fn build_failed_embed(failed: &[AlertedRelease]) -> CreateEmbed {
+    let mut e = CreateEmbed::default();
+
+    e.author(|a| {
+        a.name("Spotlit");
+        a.icon_url("https://i.imgur.com/iKUvWHR.png");
+
+        a
+    });
+
+    e.title("Failing alerts");
+    e.color(Color::from_rgb(30, 215, 96));
+    e.footer(|f| f.text("Use /alerts replay to retry the alerts that were given up on."));
+
+    let lines = failed.iter()
+        .map(|alert| {
+            let status = match alert.time_next_attempt {
+                Some(time) if alert.outcome != ALERT_DEAD => format!("retrying <t:{}:R>", time.timestamp()),
+                _ => "gave up".to_owned(),
+            };
+
+            format!(
+                "**{}** · {}{} · failed {} {}, {}\n> {}",
+                alert.artist_name, alert.name,
+                alert.id_channel.map_or(String::new(), |v| format!(" in <#{}>", v)),
+                alert.attempts, if alert.attempts == 1 { "time" } else { "times" }, status,
+                alert.last_error.as_deref().unwrap_or("Unknown error").replace('\n', " ").chars().take(200).collect::<String>(),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    // Embed descriptions are limited to 4096 characters.
+    e.description(bulk::join_truncated(&lines, 4096));
+
+    e
+}
@@ -0,0 +1,287 @@
+use std::time::Duration;
+
+use dashmap::DashMap;
+use evlog::meta;
+use once_cell::sync::Lazy;
+use rspotify::model::Id;
+use serenity::builder::{CreateComponents, CreateEmbed};
+use serenity::client::Context;
+use serenity::model::interactions::InteractionResponseType;
+use serenity::model::interactions::message_component::{ButtonStyle, MessageComponentInteraction};
+use serenity::utils::Color;
+use sqlx::Acquire;
+use tokio::time::Instant;
+
+use crate::db;
+use crate::handler::BotData;
+use crate::runtime::get_logger;
+
+pub const BULK_WATCH: &str = "bulk-watch";
+
+/// Discord interaction tokens expire after 15 minutes, after which the confirmation message can't
+/// be updated anymore.
+const PENDING_TTL: Duration = Duration::from_secs(15 * 60);
+
+/// Staged bulk watch requests awaiting confirmation, keyed by the ID of the interaction that
+/// created them.
+static PENDING: Lazy<DashMap<u64, PendingBulkWatch>> = Lazy::new(DashMap::new);
+
+pub struct BulkWatchEntry {
+    pub id_artist: String,
+    pub name: String,
+    pub id_alert_channel: u64,
+    pub markets: Vec<String>,
+    pub album_groups: Vec<String>,
+    pub id_mention_role: Option<u64>,
+    /// The name this artist was found by searching for, when it wasn't given by link or ID.
+    pub best_match_for: Option<String>,
+}
+
+pub struct PendingBulkWatch {
+    pub id_server: u64,
+    pub id_created_by: u64,
+    pub entries: Vec<BulkWatchEntry>,
+    /// Inputs that were rejected before staging, with the reason; carried into the final report.
+    pub rejected: Vec<(String, String)>,
+    time_created: Instant,
+}
+
+impl PendingBulkWatch {
+    #[must_use]
+    pub fn new(id_server: u64, id_created_by: u64, entries: Vec<BulkWatchEntry>, rejected: Vec<(String, String)>) -> Self {
+        Self {
+            id_server,
+            id_created_by,
+            entries,
+            rejected,
+            time_created: Instant::now(),
+        }
+    }
+}
+
+/// Stores a bulk watch request until it's confirmed or cancelled, and returns the Confirm/Cancel
+/// buttons that refer to it, plus a button to leave out best matches when there are any.
+pub fn stage(key: u64, pending: PendingBulkWatch) -> CreateComponents {
+    let has_best_matches = pending.entries.iter().any(|v| v.best_match_for.is_some());
+
+    PENDING.retain(|_, v| v.time_created.elapsed() < PENDING_TTL);
+    PENDING.insert(key, pending);
+
+    let mut c = CreateComponents::default();
+
+    c.create_action_row(|r| {
+        r.create_button(|b| b
+            .custom_id(format!("{}:confirm:{}", BULK_WATCH, key))
+            .label("Create watches")
+            .style(ButtonStyle::Success));
+        if has_best_matches {
+            r.create_button(|b| b
+                .custom_id(format!("{}:exact:{}", BULK_WATCH, key))
+                .label("Skip best matches")
+                .style(ButtonStyle::Primary));
+        }
+        r.create_button(|b| b
+            .custom_id(format!("{}:cancel:{}", BULK_WATCH, key))
+            .label("Cancel")
+            .style(ButtonStyle::Secondary))
+    });
+
+    c
+}
+
+/// Joins names into a list that fits in an embed field, noting how many were left out.
+#[must_use]
+pub fn join_truncated(names: &[String], max_len: usize) -> String {
+    let mut result = String::new();
+
+    for (i, name) in names.iter().enumerate() {
+        let more = format!("\n…and {} more", names.len() - i);
+
+        if result.len() + name.len() + 1 + more.len() > max_len {
+            result.push_str(&more);
+            break;
+        }
+
+        if !result.is_empty() {
+            result.push('\n');
+        }
+        result.push_str(name);
+    }
+
+    if result.is_empty() {
+        result.push_str("None");
+    }
+
+    result
+}
+
+/// Handles the Confirm/Cancel buttons; the custom ID is `bulk-watch:<confirm|exact|cancel>:<key>`,
+/// where `exact` creates only the watches whose artist wasn't found by a name search.
+pub async fn bulk_watch(ctx: Context, interaction: MessageComponentInteraction) -> anyhow::Result<()> {
+    let mut parts = interaction.data.custom_id.split(':').skip(1);
+    let action = parts.next().unwrap_or_default().to_owned();
+    let key = parts.next().and_then(|v| v.parse::<u64>().ok()).unwrap_or_default();
+
+    let is_owner = match PENDING.get(&key) {
+        None => None,
+        Some(v) => Some(v.id_created_by == *interaction.user.id.as_u64()),
+    };
+
+    match is_owner {
+        None => {
+            interaction.create_interaction_response(&ctx.http, |r| r
+                .kind(InteractionResponseType::UpdateMessage)
+                .interaction_response_data(|d| d
+                    .content("This request has expired; run the command again.")
+                    .set_components(CreateComponents::default()))).await?;
+            return Ok(());
+        }
+        Some(false) => {
+            interaction.create_interaction_response(&ctx.http, |r| r
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|d| d
+                    .content("Only the member who ran the command can confirm it.")
+                    .ephemeral(true))).await?;
+            return Ok(());
+        }
+        Some(true) => {}
+    }
+
+    let (_, mut pending) = PENDING.remove(&key).unwrap();
+
+    if action == "exact" {
+        let (best_matches, exact) = pending.entries.into_iter().partition::<Vec<_>, _>(|v| v.best_match_for.is_some());
+
+        pending.entries = exact;
+        pending.rejected.extend(best_matches.into_iter().map(|v| (
+            format!("`{}`", v.best_match_for.unwrap_or_default()),
+            format!("skipped best match {}", v.name),
+        )));
+    } else if action != "confirm" {
+        interaction.create_interaction_response(&ctx.http, |r| r
+            .kind(InteractionResponseType::UpdateMessage)
+            .interaction_response_data(|d| d
+                .content("Cancelled; no watches were created.")
+                .set_embeds(Vec::new())
+                .set_components(CreateComponents::default()))).await?;
+        return Ok(());
+    }
+
+    interaction.create_interaction_response(&ctx.http, |r| r
+        .kind(InteractionResponseType::DeferredUpdateMessage)).await?;
+
+    let data = ctx.data.read().await;
+    let data = data.get::<BotData>().unwrap();
+
+    let report = create_watches(data, pending).await?;
+
+    get_logger().info("Created bulk artist watches.", meta! {
+        "InteractionID" => interaction.id,
+        "GuildID" => interaction.guild_id,
+        "Created" => report.created.len(),
+        "Existing" => report.existing.len(),
+        "Failed" => report.failed.len(),
+    });
+
+    interaction.edit_original_interaction_response(&ctx.http, |r| r
+        .content("")
+        .add_embed(report.embed())
+        .components(|c| c)).await?;
+
+    Ok(())
+}
+
+struct BulkWatchReport {
+    created: Vec<String>,
+    existing: Vec<String>,
+    failed: Vec<String>,
+}
+
+impl BulkWatchReport {
+    fn embed(&self) -> CreateEmbed {
+        let mut e = CreateEmbed::default();
+
+        e.author(|a| {
+            a.name("Spotlit");
+            a.icon_url("https://i.imgur.com/iKUvWHR.png");
+
+            a
+        });
+
+        e.title("Bulk watch results");
+        e.color(Color::from_rgb(30, 215, 96));
+        e.field(format!("Now watching ({})", self.created.len()), join_truncated(&self.created, 1024), false);
+        e.field(format!("Already watched ({})", self.existing.len()), join_truncated(&self.existing, 1024), false);
+        e.field(format!("Failed ({})", self.failed.len()), join_truncated(&self.failed, 1024), false);
+
+        e
+    }
+}
+
+async fn create_watches(data: &BotData, pending: PendingBulkWatch) -> anyhow::Result<BulkWatchReport> {
+    let mut report = BulkWatchReport {
+        created: Vec::new(),
+        existing: Vec::new(),
+        failed: pending.rejected.iter().map(|(input, reason)| format!("{}: {}", input, reason)).collect(),
+    };
+
+    let watched = db::model::list_server_watched_artists(data.db_client.conn(), pending.id_server).await?;
+
+    let (existing, new): (Vec<_>, Vec<_>) = pending.entries.into_iter()
+        .partition(|v| watched.iter().any(|w| w.id_artist == v.id_artist));
+    report.existing.extend(existing.into_iter().map(|v| v.name));
+
+    // Watches reference the artist cache, so the artists have to be saved first.
+    let ids = new.iter().map(|v| v.id_artist.clone()).collect::<Vec<_>>();
+    let artists = data.spotify_client.artists(&ids).await?;
+
+    for artist in &artists {
+        crate::monitor::save_artist(&data.db_client, artist).await?;
+    }
+
+    let mut tx = data.db_client.conn().begin().await?;
+
+    for entry in new {
+        if !artists.iter().any(|v| v.id.id() == entry.id_artist) {
+            report.failed.push(format!("{}: not found on Spotify", entry.name));
+            continue;
+        }
+
+        // A savepoint per row, so one failed insert doesn't abort the whole transaction.
+        let mut savepoint = tx.begin().await?;
+
+        match db::model::add_watch(
+            &mut savepoint,
+            pending.id_created_by,
+            pending.id_server,
+            entry.id_alert_channel,
+            &entry.id_artist,
+            entry.markets,
+            entry.album_groups,
+            entry.id_mention_role,
+        ).await {
+            Ok(Some(_)) => {
+                savepoint.commit().await?;
+                report.created.push(entry.name);
+            }
+            Ok(None) => {
+                savepoint.commit().await?;
+                report.existing.push(entry.name);
+            }
+            Err(e) => {
+                savepoint.rollback().await?;
+
+                get_logger().error("Failed to save artist watch in bulk.", meta! {
+                    "GuildID" => pending.id_server,
+                    "ArtistID" => entry.id_artist,
+                    "Error" => e,
+                });
+                report.failed.push(format!("{}: could not be saved", entry.name));
+            }
+        }
+    }
+
+    tx.commit().await?;
+
+    Ok(report)
+}
@@ -0,0 +1,170 @@
+use std::collections::HashMap;
+
+use chrono::Utc;
+use evlog::meta;
+use serde_json::{json, Value};
+use serenity::client::Context;
+use serenity::model::interactions::application_command::ApplicationCommandInteraction;
+
+use crate::db;
+use crate::db::schema::{AlertedRelease, Watch};
+use crate::handler::BotData;
+use crate::helpers::command_resp;
+use crate::runtime::get_logger;
+
+/// Handles `/watches export`. Replies with everything stored about the server's watches: a JSON
+/// file with the watches and their alerted releases nested, and the same data as two CSV files.
+///
+/// The JSON and watches CSV use the column names `/watches import` reads, so either can be
+/// imported again with the same channels, markets, release types and mention roles.
+pub async fn export(ctx: Context, interaction: ApplicationCommandInteraction) -> anyhow::Result<()> {
+    command_resp::reply_deferred_ack(&ctx, &interaction).await?;
+
+    let guild_id = match interaction.guild_id {
+        None => {
+            get_logger().info("Interaction was not used in a guild.", meta! {
+                "InteractionID" => interaction.id,
+            });
+            command_resp::reply_deferred_result(&ctx, &interaction, "/watches export can only be used in a server.").await.unwrap();
+            return Ok(());
+        }
+        Some(v) => v,
+    };
+
+    //
+
+    let data = ctx.data.read().await;
+    let data = data.get::<BotData>().unwrap();
+
+    let watches = db::model::list_watches(data.db_client.conn(), *guild_id.as_u64()).await?;
+    if watches.is_empty() {
+        command_resp::reply_deferred_result(&ctx, &interaction, "This server is not monitoring any artists; there's nothing to export.").await.unwrap();
+        return Ok(());
+    }
+
+    let artist_names = db::model::list_server_watched_artists(data.db_client.conn(), *guild_id.as_u64()).await?
+        .into_iter()
+        .map(|v| (v.id_artist, v.artist_name))
+        .collect::<HashMap<_, _>>();
+    let alerted = db::model::list_server_alerted_releases(data.db_client.conn(), *guild_id.as_u64()).await?;
+
+    let json = serde_json::to_vec_pretty(&build_json(*guild_id.as_u64(), &watches, &artist_names, &alerted))?;
+    let watches_csv = build_watches_csv(&watches, &artist_names)?;
+    let alerts_csv = build_alerts_csv(&watches, &artist_names, &alerted)?;
+
+    get_logger().info("Exported artist watches.", meta! {
+        "InteractionID" => interaction.id,
+        "GuildID" => guild_id,
+        "Watches" => watches.len(),
+        "Alerts" => alerted.len(),
+    });
+
+    let json_name = format!("spotlit-{}.json", guild_id);
+    let watches_name = format!("spotlit-{}-watches.csv", guild_id);
+    let alerts_name = format!("spotlit-{}-alerts.csv", guild_id);
+
+    interaction.create_followup_message(&ctx.http, |r| r
+        .content(format!("Exported {} watches and {} alerted releases.", watches.len(), alerted.len()))
+        .add_file((json.as_slice(), json_name.as_str()))
+        .add_file((watches_csv.as_slice(), watches_name.as_str()))
+        .add_file((alerts_csv.as_slice(), alerts_name.as_str()))).await?;
+
+    Ok(())
+}
+
+fn build_json(id_server: u64, watches: &[Watch], artist_names: &HashMap<String, String>, alerted: &[AlertedRelease]) -> Value {
+    let watches = watches.iter()
+        .map(|w| json!({
+            "id": w.id,
+            "artist": w.id_artist,
+            "artist_name": artist_names.get(&w.id_artist),
+            "markets": w.markets,
+            "release_types": w.album_groups,
+            "pause_mode": w.pause_mode,
+            "mention_role": w.id_mention_role.map(|v| v.to_string()),
+            "disabled_reason": w.disabled_reason,
+            "channel": w.id_alert_channel.to_string(),
+            "created_by": w.id_created_by.to_string(),
+            "time_created": w.time_created.to_rfc3339(),
+            "time_last_scanned": if w.time_last_scanned.timestamp() == 0 {
+                None
+            } else {
+                Some(w.time_last_scanned.to_rfc3339())
+            },
+            "alerted_releases": alerted.iter()
+                .filter(|v| v.id_watch == w.id)
+                .map(|v| json!({
+                    "id": v.id_release,
+                    "name": v.name,
+                    "release_type": v.album_group,
+                    "release_date": v.release_date,
+                    "href": v.href,
+                    "outcome": v.outcome,
+                    "channel": v.id_channel.map(|v| v.to_string()),
+                    "message": v.id_message.map(|v| v.to_string()),
+                    "time_sent": v.time_sent.to_rfc3339(),
+                    "attempts": v.attempts,
+                    "last_error": v.last_error,
+                }))
+                .collect::<Vec<_>>(),
+        }))
+        .collect::<Vec<_>>();
+
+    json!({
+        "server": id_server.to_string(),
+        "time_exported": Utc::now().to_rfc3339(),
+        "watches": watches,
+    })
+}
+
+fn build_watches_csv(watches: &[Watch], artist_names: &HashMap<String, String>) -> anyhow::Result<Vec<u8>> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+
+    writer.write_record(&["id", "artist", "artist_name", "markets", "release_types", "mention_role", "channel", "created_by", "time_created", "time_last_scanned"])?;
+
+    for w in watches {
+        writer.write_record(&[
+            w.id.to_string(),
+            w.id_artist.clone(),
+            artist_names.get(&w.id_artist).cloned().unwrap_or_default(),
+            w.markets.join(" "),
+            w.album_groups.join(" "),
+            w.id_mention_role.map(|v| v.to_string()).unwrap_or_default(),
+            w.id_alert_channel.to_string(),
+            w.id_created_by.to_string(),
+            w.time_created.to_rfc3339(),
+            if w.time_last_scanned.timestamp() == 0 { String::new() } else { w.time_last_scanned.to_rfc3339() },
+        ])?;
+    }
+
+    Ok(writer.into_inner().map_err(|e| e.into_error())?)
+}
+
+fn build_alerts_csv(watches: &[Watch], artist_names: &HashMap<String, String>, alerted: &[AlertedRelease]) -> anyhow::Result<Vec<u8>> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+
+    writer.write_record(&["watch", "artist", "artist_name", "release", "name", "release_type", "release_date", "href", "outcome", "channel", "message", "time_sent", "attempts", "last_error"])?;
+
+    for v in alerted {
+        let id_artist = watches.iter().find(|w| w.id == v.id_watch).map_or("", |w| w.id_artist.as_str());
+
+        writer.write_record(&[
+            v.id_watch.to_string(),
+            id_artist.to_owned(),
+            artist_names.get(id_artist).cloned().unwrap_or_default(),
+            v.id_release.clone(),
+            v.name.clone(),
+            v.album_group.clone(),
+            v.release_date.clone(),
+            v.href.clone(),
+            v.outcome.clone(),
+            v.id_channel.map(|v| v.to_string()).unwrap_or_default(),
+            v.id_message.map(|v| v.to_string()).unwrap_or_default(),
+            v.time_sent.to_rfc3339(),
+            v.attempts.to_string(),
+            v.last_error.clone().unwrap_or_default(),
+        ])?;
+    }
+
+    Ok(writer.into_inner().map_err(|e| e.into_error())?)
+}
@@ -0,0 +1,255 @@
+use evlog::meta;
+use rspotify::model::Id;
+use serenity::builder::CreateApplicationCommand;
+use serenity::client::Context;
+use serenity::model::interactions::application_command::{ApplicationCommandInteraction, ApplicationCommandOptionType};
+use serenity::model::interactions::autocomplete::AutocompleteInteraction;
+use serenity::model::interactions::InteractionResponseType;
+use serenity::model::interactions::message_component::MessageComponentInteraction;
+
+use crate::commands::settings;
+use crate::db;
+use crate::db::schema::FollowedArtist;
+use crate::handler::BotData;
+use crate::helpers::{command_opt, command_resp};
+use crate::runtime::get_logger;
+use crate::spotify;
+use crate::spotify::release_types;
+
+pub const FOLLOW: &str = "follow";
+
+pub fn follow_builder(cmd: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    cmd.name(FOLLOW)
+        .description("Get DMs about an artist's new releases")
+        .create_option(|opt| opt
+            .name("artist")
+            .description("Start typing an artist's name, or paste a link (https://open.spotify.com/artist/...)")
+            .required(true)
+            .set_autocomplete(true)
+            .kind(ApplicationCommandOptionType::String))
+        .create_option(|opt| opt
+            .name("markets")
+            .description("Country codes or presets (ex: US; US, GB; EU; LATAM; NORDICS; default: the server's default)")
+            .required(false)
+            .kind(ApplicationCommandOptionType::String))
+        .create_option(|opt| opt
+            .name("release-types")
+            .description("Release types to alert on: album, single, compilation, appears_on (default: server default)")
+            .required(false)
+            .kind(ApplicationCommandOptionType::String))
+}
+
+pub async fn follow(ctx: Context, interaction: ApplicationCommandInteraction) -> anyhow::Result<()> {
+    let artist = command_opt::find_required(&ctx, &interaction, command_opt::find_string_opt, "artist").await?.unwrap();
+    let market = command_opt::find_string_opt(&interaction.data.options, "markets");
+    let types_input = command_opt::find_string_opt(&interaction.data.options, "release-types");
+
+    command_resp::reply_deferred_ack(&ctx, &interaction).await?;
+
+    let user_id = interaction.user.id;
+
+    //
+
+    let data = ctx.data.read().await;
+    let data = data.get::<BotData>().unwrap();
+
+    // Used outside servers too, where there are no server defaults.
+    let guild_settings = match interaction.guild_id {
+        None => None,
+        Some(guild_id) => Some((guild_id, db::model::get_guild_settings(data.db_client.conn(), *guild_id.as_u64()).await?)),
+    };
+
+    let markets = match &market {
+        Some(market) => match spotify::markets::parse_markets(market) {
+            Ok(v) => v,
+            Err(e) => {
+                command_resp::reply_deferred_result(&ctx, &interaction, e).await.unwrap();
+                return Ok(());
+            }
+        },
+        None => {
+            let default = match &guild_settings {
+                None => None,
+                Some((guild_id, guild_settings)) => settings::default_markets(&ctx, *guild_id, guild_settings).await,
+            };
+
+            match default {
+                Some(v) => v,
+                None => {
+                    command_resp::reply_deferred_result(&ctx, &interaction, "Pass the markets to check for releases in, like `US`.").await.unwrap();
+                    return Ok(());
+                }
+            }
+        }
+    };
+
+    let album_groups = match types_input {
+        None => guild_settings.as_ref()
+            .and_then(|(_, v)| v.default_album_groups.clone())
+            .unwrap_or_else(release_types::default_release_types),
+        Some(v) => match release_types::parse_release_types(&v) {
+            Ok(v) => v,
+            Err(e) => {
+                command_resp::reply_deferred_result(&ctx, &interaction, e).await.unwrap();
+                return Ok(());
+            }
+        },
+    };
+
+    let artist_details = match data.spotify_client.resolve_artist(&artist).await {
+        Ok(Some(v)) => v,
+        Ok(None) => {
+            command_resp::reply_deferred_result(&ctx, &interaction, "Could not find that artist on Spotify; pick one from the list or paste a link like `https://open.spotify.com/artist/...`").await.unwrap();
+            return Ok(());
+        }
+        Err(e) => {
+            get_logger().error("Failed to look up artist passed to /follow.", meta! {
+                "InteractionID" => interaction.id,
+                "UserID" => user_id,
+                "Artist" => artist,
+                "Error" => e,
+            });
+            command_resp::reply_deferred_result(&ctx, &interaction, "Failed to look up that artist on Spotify.").await.unwrap();
+            return Ok(());
+        }
+    };
+    let artist_id = artist_details.id.id();
+
+    if let Err(e) = crate::monitor::save_artist(&data.db_client, &artist_details).await {
+        get_logger().error("Failed to save artist details.", meta! {
+            "InteractionID" => interaction.id,
+            "UserID" => user_id,
+            "ArtistID" => artist_id,
+            "Error" => e,
+        });
+
+        command_resp::reply_deferred_result(&ctx, &interaction, "Failed to follow that artist.").await.unwrap();
+        return Ok(());
+    }
+
+    // Following anything is taken as a sign the user has reopened their DMs.
+    db::model::set_user_dm_blocked(data.db_client.conn(), *user_id.as_u64(), false).await?;
+
+    match db::model::add_subscription(
+        data.db_client.conn(),
+        *user_id.as_u64(),
+        artist_id,
+        markets.clone(),
+        album_groups.clone(),
+    ).await? {
+        None => {
+            command_resp::reply_deferred_result(&ctx, &interaction, format!(
+                "You already follow **{}**.", artist_details.name
+            )).await.unwrap();
+        }
+        Some(subscription) => {
+            get_logger().info("Saved new user subscription.", meta! {
+                "InteractionID" => interaction.id,
+                "UserID" => user_id,
+                "ArtistID" => artist_id,
+                "SubscriptionID" => subscription.id,
+            });
+
+            command_resp::reply_deferred_result(&ctx, &interaction, format!(
+                "You'll get a DM when **{}** releases {} in {}. Make sure your DMs are open to members of a server I'm in.",
+                artist_details.name,
+                release_types::describe_release_types(&album_groups),
+                spotify::markets::describe_markets(&markets),
+            )).await.unwrap();
+        }
+    }
+
+    Ok(())
+}
+
+/// Handles the "Notify me too" button on channel alerts; the custom ID is `follow:<artist ID>`.
+/// The subscription copies the markets and release types of the server's watch.
+pub async fn follow_button(ctx: Context, interaction: MessageComponentInteraction) -> anyhow::Result<()> {
+    let artist_id = interaction.data.custom_id.split(':').nth(1).unwrap_or_default().to_owned();
+
+    let guild_id = match interaction.guild_id {
+        None => return Ok(()),
+        Some(v) => v,
+    };
+    let user_id = interaction.user.id;
+
+    let data = ctx.data.read().await;
+    let data = data.get::<BotData>().unwrap();
+
+    let watched = db::model::list_server_watched_artists(data.db_client.conn(), *guild_id.as_u64()).await?;
+
+    let watch = match watched.iter().find(|v| v.id_artist == artist_id) {
+        None => None,
+        Some(v) => db::model::get_watch(data.db_client.conn(), v.id_watch).await?.map(|w| (v, w)),
+    };
+
+    let content = match watch {
+        None => "This server no longer monitors this artist; use /follow to follow them yourself.".to_owned(),
+        Some((watched, watch)) => {
+            db::model::set_user_dm_blocked(data.db_client.conn(), *user_id.as_u64(), false).await?;
+
+            match db::model::add_subscription(
+                data.db_client.conn(),
+                *user_id.as_u64(),
+                &watch.id_artist,
+                watch.markets,
+                watch.album_groups,
+            ).await? {
+                None => format!("You already follow **{}**.", watched.artist_name),
+                Some(subscription) => {
+                    get_logger().info("Saved new user subscription from alert button.", meta! {
+                        "InteractionID" => interaction.id,
+                        "GuildID" => guild_id,
+                        "UserID" => user_id,
+                        "ArtistID" => watch.id_artist,
+                        "SubscriptionID" => subscription.id,
+                    });
+
+                    format!(
+                        "You'll get a DM when **{}** releases something new. Make sure your DMs are open to members of this server; use /unfollow to stop.",
+                        watched.artist_name,
+                    )
+                }
+            }
+        }
+    };
+
+    interaction.create_interaction_response(&ctx.http, |r| r
+        .kind(InteractionResponseType::ChannelMessageWithSource)
+        .interaction_response_data(|d| d
+            .content(content)
+            .ephemeral(true))).await?;
+
+    Ok(())
+}
+
+/// Finds the subscription an `artist` option refers to; see `watch::find_watched_artist`.
+#[must_use]
+pub fn find_followed_artist<'a>(followed: &'a [FollowedArtist], input: &str) -> Option<&'a FollowedArtist> {
+    followed.iter().find(|v| v.id_artist == input)
+        .or_else(|| followed.iter().find(|v| v.artist_name.eq_ignore_ascii_case(input)))
+}
+
+/// Autocompletes an `artist` option with the artists the user follows.
+pub async fn followed_artist_autocomplete(ctx: Context, interaction: AutocompleteInteraction) -> anyhow::Result<()> {
+    let input = match command_opt::find_focused_opt(&interaction.data.options) {
+        None => return Ok(()),
+        Some(v) => v.value.to_lowercase(),
+    };
+
+    let data = ctx.data.read().await;
+    let data = data.get::<BotData>().unwrap();
+
+    let followed = db::model::list_user_followed_artists(data.db_client.conn(), *interaction.user.id.as_u64()).await?;
+
+    interaction.create_autocomplete_response(&ctx.http, |r| {
+        followed.iter()
+            .filter(|v| v.artist_name.to_lowercase().contains(&input))
+            .take(25)
+            .for_each(|v| { r.add_string_choice(&v.artist_name, &v.id_artist); });
+
+        r
+    }).await?;
+
+    Ok(())
+}
@@ -0,0 +1,491 @@
+use std::collections::HashMap;
+
+use evlog::meta;
+use futures::StreamExt;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use rspotify::model::Id;
+use serde_json::Value;
+use serenity::builder::CreateEmbed;
+use serenity::client::Context;
+use serenity::model::channel::Attachment;
+use serenity::model::id::{ChannelId, RoleId};
+use serenity::model::interactions::application_command::ApplicationCommandInteraction;
+use serenity::utils::Color;
+
+use crate::commands::bulk;
+use crate::commands::bulk::{BulkWatchEntry, PendingBulkWatch};
+use crate::commands::settings;
+use crate::db;
+use crate::handler::BotData;
+use crate::helpers::{command_opt, command_resp, permissions};
+use crate::runtime::get_logger;
+use crate::spotify;
+use crate::spotify::release_types;
+
+/// Imports larger than this are rejected before they're parsed.
+const MAX_FILE_SIZE: u64 = 1024 * 1024;
+const MAX_ROWS: usize = 1000;
+
+/// Rows with artist names need a Spotify search each, unlike links and IDs, which are looked up in
+/// batches. Only this many distinct names are searched per import, a few at a time.
+const MAX_NAME_SEARCHES: usize = 100;
+const NAME_SEARCH_CONCURRENCY: usize = 4;
+
+static MATCH_CHANNEL: Lazy<Regex> = Lazy::new(|| Regex::new(r#"^(?:<#)?(\d{15,20})>?$"#).unwrap());
+static MATCH_ROLE: Lazy<Regex> = Lazy::new(|| Regex::new(r#"^(?:<@&)?(\d{15,20})>?$"#).unwrap());
+
+/// A row of an import file, before it's validated.
+struct ImportRow {
+    artist: String,
+    markets: Option<String>,
+    channel: Option<String>,
+    release_types: Option<String>,
+    mention_role: Option<String>,
+}
+
+/// Handles `/watches import`. Every row is validated against Spotify and the server's channels
+/// first; the valid rows are staged for confirmation and created in bulk.
+pub async fn import(ctx: Context, interaction: ApplicationCommandInteraction) -> anyhow::Result<()> {
+    let opts = command_opt::command_options(&interaction);
+
+    let file = command_opt::find_required(&ctx, &interaction, command_opt::find_attachment_opt, "file").await?.unwrap();
+    let default_markets = command_opt::find_string_opt(opts, "markets");
+    let default_channel = command_opt::find_channel_opt(opts, "channel").map(|v| v.id);
+    let types_input = command_opt::find_string_opt(opts, "release-types");
+
+    command_resp::reply_deferred_ack(&ctx, &interaction).await?;
+
+    //
+
+    let guild_id = match permissions::authorize_watch_manager(&ctx, &interaction, "/watches import").await? {
+        None => return Ok(()),
+        Some(v) => v,
+    };
+
+    let member_id = interaction.member.as_ref().unwrap();
+
+    //
+
+    let data = ctx.data.read().await;
+    let data = data.get::<BotData>().unwrap();
+
+    let guild_settings = db::model::get_guild_settings(data.db_client.conn(), *guild_id.as_u64()).await?;
+
+    let default_channel = default_channel
+        .or_else(|| guild_settings.default_alert_channel.map(ChannelId))
+        .unwrap_or(interaction.channel_id);
+
+    let default_markets = match default_markets.map(|v| spotify::markets::parse_markets(&v)) {
+        None => settings::default_markets(&ctx, guild_id, &guild_settings).await,
+        Some(Ok(v)) => Some(v),
+        Some(Err(e)) => {
+            command_resp::reply_deferred_result(&ctx, &interaction, e).await.unwrap();
+            return Ok(());
+        }
+    };
+
+    let default_album_groups = match types_input {
+        None => guild_settings.default_album_groups.clone().unwrap_or_else(release_types::default_release_types),
+        Some(v) => match release_types::parse_release_types(&v) {
+            Ok(v) => v,
+            Err(e) => {
+                command_resp::reply_deferred_result(&ctx, &interaction, e).await.unwrap();
+                return Ok(());
+            }
+        },
+    };
+
+    let rows = match read_rows(&file).await {
+        Ok(v) => v,
+        Err(e) => {
+            get_logger().info("Invalid import file passed to /watches import.", meta! {
+                "InteractionID" => interaction.id,
+                "GuildID" => guild_id,
+                "FileName" => file.filename,
+                "Error" => e,
+            });
+            command_resp::reply_deferred_result(&ctx, &interaction, format!("Could not read `{}`: {}", file.filename, e)).await.unwrap();
+            return Ok(());
+        }
+    };
+
+    if rows.is_empty() {
+        command_resp::reply_deferred_result(&ctx, &interaction, "That file has no artists to import.").await.unwrap();
+        return Ok(());
+    }
+
+    if rows.len() > MAX_ROWS {
+        command_resp::reply_deferred_result(&ctx, &interaction, format!("Imports are limited to {} artists; split the file and import each part.", MAX_ROWS)).await.unwrap();
+        return Ok(());
+    }
+
+    //
+
+    let mut entries: Vec<BulkWatchEntry> = Vec::new();
+    let mut rejected = Vec::new();
+
+    // Artist IDs can be looked up in batches; names need a search each.
+    let ids = rows.iter()
+        .filter_map(|v| spotify::parse_artist_id(&v.artist))
+        .collect::<Vec<_>>();
+    let found = data.spotify_client.artists(&ids).await?
+        .into_iter()
+        .map(|v| (v.id.id().to_owned(), v.name))
+        .collect::<HashMap<_, _>>();
+
+    let mut names: Vec<String> = Vec::new();
+    for row in &rows {
+        let name = name_key(&row.artist);
+        if spotify::parse_artist_id(&row.artist).is_none() && !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    let searched = search_names(data, names).await;
+
+    let mut channels: HashMap<ChannelId, Result<(), String>> = HashMap::new();
+
+    for (i, row) in rows.iter().enumerate() {
+        let label = format!("Row {} (`{}`)", i + 1, row.artist);
+
+        let markets = match (&row.markets, &default_markets) {
+            (Some(v), _) => match spotify::markets::parse_markets(v) {
+                Ok(v) => v,
+                Err(e) => {
+                    rejected.push((label, e));
+                    continue;
+                }
+            },
+            (None, Some(v)) => v.clone(),
+            (None, None) => {
+                rejected.push((label, "no markets; add a markets column or pass the markets option".to_owned()));
+                continue;
+            }
+        };
+
+        let channel_id = match &row.channel {
+            None => default_channel,
+            Some(v) => match parse_channel(v) {
+                Some(v) => v,
+                None => {
+                    rejected.push((label, format!("`{}` is not a channel mention or ID", v)));
+                    continue;
+                }
+            },
+        };
+
+        let album_groups = match &row.release_types {
+            None => default_album_groups.clone(),
+            Some(v) => match release_types::parse_release_types(v) {
+                Ok(v) => v,
+                Err(e) => {
+                    rejected.push((label, e));
+                    continue;
+                }
+            },
+        };
+
+        let id_mention_role = match &row.mention_role {
+            None => None,
+            Some(v) => match parse_role(v) {
+                // Role IDs of another server, as in an export from there, can't be mentioned here.
+                Some(role) if ctx.cache.guild_field(guild_id, |g| g.roles.contains_key(&role)) == Some(true) => Some(*role.as_u64()),
+                _ => {
+                    rejected.push((label, format!("`{}` is not a role in this server", v)));
+                    continue;
+                }
+            },
+        };
+
+        if !channels.contains_key(&channel_id) {
+            let status = permissions::check_alert_channel(&ctx.cache, guild_id, channel_id)
+                .map_err(|e| e.description(channel_id));
+            channels.insert(channel_id, status);
+        }
+        if let Err(e) = &channels[&channel_id] {
+            rejected.push((label, e.clone()));
+            continue;
+        }
+
+        let (id_artist, name, best_match_for) = match spotify::parse_artist_id(&row.artist) {
+            Some(id) => match found.get(&id) {
+                None => {
+                    rejected.push((label, "not found on Spotify".to_owned()));
+                    continue;
+                }
+                Some(name) => (id, name.clone(), None),
+            },
+            None => match searched.get(&name_key(&row.artist)) {
+                None => {
+                    rejected.push((label, format!("only the first {} artist names are searched; use a link or ID", MAX_NAME_SEARCHES)));
+                    continue;
+                }
+                Some(Ok(None)) => {
+                    rejected.push((label, "not found on Spotify".to_owned()));
+                    continue;
+                }
+                Some(Ok(Some((id, name)))) => (id.clone(), name.clone(), Some(row.artist.trim().to_owned())),
+                Some(Err(e)) => {
+                    get_logger().warn("Failed to look up imported artist.", meta! {
+                        "InteractionID" => interaction.id,
+                        "GuildID" => guild_id,
+                        "Artist" => row.artist,
+                        "Error" => e.to_string(),
+                    });
+                    rejected.push((label, "Spotify lookup failed".to_owned()));
+                    continue;
+                }
+            },
+        };
+
+        if entries.iter().any(|v| v.id_artist == id_artist) {
+            rejected.push((label, format!("{} is already listed in an earlier row", name)));
+            continue;
+        }
+
+        entries.push(BulkWatchEntry {
+            id_artist,
+            name,
+            id_alert_channel: *channel_id.as_u64(),
+            markets,
+            album_groups,
+            id_mention_role,
+            best_match_for,
+        });
+    }
+
+    get_logger().info("Validated watch import.", meta! {
+        "InteractionID" => interaction.id,
+        "GuildID" => guild_id,
+        "FileName" => file.filename,
+        "Rows" => rows.len(),
+        "Valid" => entries.len(),
+        "Rejected" => rejected.len(),
+    });
+
+    let rejected_lines = rejected.iter()
+        .map(|(label, reason)| format!("{}: {}", label, reason))
+        .collect::<Vec<_>>();
+
+    if entries.is_empty() {
+        interaction.edit_original_interaction_response(&ctx.http, |r| r
+            .add_embed(build_embed(&[], &rejected_lines))).await?;
+        return Ok(());
+    }
+
+    let embed = build_embed(&entries, &rejected_lines);
+
+    let components = bulk::stage(*interaction.id.as_u64(), PendingBulkWatch::new(
+        *guild_id.as_u64(),
+        *member_id.user.id.as_u64(),
+        entries,
+        rejected,
+    ));
+
+    interaction.edit_original_interaction_response(&ctx.http, |r| r
+        .add_embed(embed)
+        .components(|c| {
+            *c = components;
+            c
+        })).await?;
+
+    Ok(())
+}
+
+/// Builds the dry-run summary shown before an import is confirmed.
+fn build_embed(entries: &[BulkWatchEntry], rejected: &[String]) -> CreateEmbed {
+    let mut e = CreateEmbed::default();
+
+    e.author(|a| {
+        a.name("Spotlit");
+        a.icon_url("https://i.imgur.com/iKUvWHR.png");
+
+        a
+    });
+
+    e.color(Color::from_rgb(30, 215, 96));
+
+    if entries.is_empty() {
+        e.title("Nothing to import");
+    } else {
+        e.title(format!("Import {} watches?", entries.len()));
+
+        let lines = entries.iter()
+            .map(|v| format!(
+                "{}{} · {} · {} · <#{}>",
+                v.name,
+                v.best_match_for.as_ref().map_or(String::new(), |input| format!(" (best match for `{}`)", input)),
+                spotify::markets::describe_markets(&v.markets),
+                release_types::describe_release_types(&v.album_groups),
+                v.id_alert_channel,
+            ))
+            .collect::<Vec<_>>();
+        e.description(bulk::join_truncated(&lines, 3000));
+
+        if entries.iter().any(|v| v.best_match_for.is_some()) {
+            e.footer(|f| f.text("Artists given by name are the top search result; check them, or press Skip best matches to only create the watches given by link or ID."));
+        }
+    }
+
+    e.field(format!("Skipped rows ({})", rejected.len()), bulk::join_truncated(rejected, 1024), false);
+
+    e
+}
+
+fn name_key(input: &str) -> String {
+    input.trim().to_lowercase()
+}
+
+/// Searches for each artist name, returning the top result by name. Only the first
+/// `MAX_NAME_SEARCHES` names are searched, `NAME_SEARCH_CONCURRENCY` at a time.
+async fn search_names(data: &BotData, names: Vec<String>) -> HashMap<String, anyhow::Result<Option<(String, String)>>> {
+    futures::stream::iter(names.into_iter().take(MAX_NAME_SEARCHES))
+        .map(|name| async move {
+            let result = data.spotify_client.search_artists(&name, 1).await
+                .map(|v| v.into_iter().next().map(|v| (v.id.id().to_owned(), v.name)));
+
+            (name, result)
+        })
+        .buffer_unordered(NAME_SEARCH_CONCURRENCY)
+        .collect()
+        .await
+}
+
+fn parse_channel(input: &str) -> Option<ChannelId> {
+    MATCH_CHANNEL.captures(input.trim())
+        .and_then(|v| v.get(1))
+        .and_then(|v| v.as_str().parse::<u64>().ok())
+        .map(ChannelId)
+}
+
+fn parse_role(input: &str) -> Option<RoleId> {
+    MATCH_ROLE.captures(input.trim())
+        .and_then(|v| v.get(1))
+        .and_then(|v| v.as_str().parse::<u64>().ok())
+        .map(RoleId)
+}
+
+async fn read_rows(file: &Attachment) -> anyhow::Result<Vec<ImportRow>> {
+    if file.size > MAX_FILE_SIZE {
+        anyhow::bail!("the file is larger than 1 MB");
+    }
+
+    let content = file.download().await?;
+    let content = String::from_utf8(content)
+        .map_err(|_| anyhow::anyhow!("the file is not UTF-8 text"))?;
+
+    if file.filename.to_lowercase().ends_with(".json") || content.trim_start().starts_with(|c| c == '[' || c == '{') {
+        parse_json(&content)
+    } else {
+        parse_csv(&content)
+    }
+}
+
+/// Parses a JSON array whose elements are either artist strings or objects with an `artist` key
+/// and optional `markets` and `release_types` (strings or arrays of strings), `channel` and
+/// `mention_role` keys. The array may also be under a `watches` key, as in `/watches export` files.
+fn parse_json(content: &str) -> anyhow::Result<Vec<ImportRow>> {
+    let items = match serde_json::from_str::<Value>(content)? {
+        Value::Array(v) => v,
+        Value::Object(mut v) => match v.remove("watches") {
+            Some(Value::Array(v)) => v,
+            _ => anyhow::bail!("expected a JSON array of artists"),
+        },
+        _ => anyhow::bail!("expected a JSON array of artists"),
+    };
+
+    let mut rows = Vec::new();
+
+    for (i, item) in items.into_iter().enumerate() {
+        let row = match item {
+            Value::String(v) => ImportRow {
+                artist: v,
+                markets: None,
+                channel: None,
+                release_types: None,
+                mention_role: None,
+            },
+            Value::Object(v) => ImportRow {
+                artist: match v.get("artist") {
+                    Some(Value::String(v)) => v.clone(),
+                    _ => anyhow::bail!("element {} has no `artist` string", i + 1),
+                },
+                markets: json_list(v.get("markets")),
+                channel: json_id(v.get("channel")),
+                release_types: json_list(v.get("release_types")),
+                mention_role: json_id(v.get("mention_role")),
+            },
+            _ => anyhow::bail!("element {} is not a string or an object", i + 1),
+        };
+
+        rows.push(row);
+    }
+
+    Ok(rows)
+}
+
+/// Reads a JSON string, or an array of strings as a comma-separated list.
+fn json_list(value: Option<&Value>) -> Option<String> {
+    match value {
+        Some(Value::String(v)) => Some(v.clone()),
+        Some(Value::Array(v)) => Some(v.iter().filter_map(Value::as_str).collect::<Vec<_>>().join(",")),
+        _ => None,
+    }
+}
+
+/// Reads a Discord ID, which may be a string or a number.
+fn json_id(value: Option<&Value>) -> Option<String> {
+    match value {
+        Some(Value::String(v)) => Some(v.clone()),
+        Some(Value::Number(v)) => Some(v.to_string()),
+        _ => None,
+    }
+}
+
+/// Parses CSV with `artist`, `markets`, `channel`, `release_types` and `mention_role` columns.
+/// Without a header row, the columns are read in that order.
+fn parse_csv(content: &str) -> anyhow::Result<Vec<ImportRow>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(content.as_bytes());
+
+    let mut records = reader.records().collect::<Result<Vec<_>, _>>()?.into_iter().peekable();
+
+    let header = records.peek()
+        .map(|v| v.iter().map(str::to_lowercase).collect::<Vec<_>>())
+        .filter(|v| v.iter().any(|c| c == "artist"));
+
+    let (i_artist, i_markets, i_channel, i_release_types, i_mention_role) = match &header {
+        None => (Some(0), Some(1), Some(2), Some(3), Some(4)),
+        Some(v) => {
+            let find = |name: &str| v.iter().position(|c| c == name);
+            (
+                find("artist"),
+                find("markets").or_else(|| find("market")),
+                find("channel"),
+                find("release_types").or_else(|| find("release-types")),
+                find("mention_role").or_else(|| find("mention-role")),
+            )
+        }
+    };
+    if header.is_some() {
+        records.next();
+    }
+
+    let field = |record: &csv::StringRecord, i: Option<usize>| i
+        .and_then(|i| record.get(i))
+        .filter(|v| !v.is_empty())
+        .map(str::to_owned);
+
+    Ok(records
+        .filter_map(|v| Some(ImportRow {
+            artist: field(&v, i_artist)?,
+            markets: field(&v, i_markets),
+            channel: field(&v, i_channel),
+            release_types: field(&v, i_release_types),
+            mention_role: field(&v, i_mention_role),
+        }))
+        .collect())
+}
@@ -1,17 +1,137 @@
 use dashmap::DashMap;
 use once_cell::sync::Lazy;
-use crate::helpers::command_def::{CommandDef, InteractionHandler};
+use serenity::client::Context;
+use serenity::model::channel::Message;
 
+use crate::helpers::command_def::{AutocompleteHandler, CommandDef, ComponentDef, ComponentHandler, InteractionHandler};
+
+mod alerts;
+mod bulk;
+mod export;
+mod follow;
+mod import;
 mod monitor;
+mod monitor_message;
+mod monitor_playlist;
+mod releases;
+mod settings;
+mod unfollow;
+mod unmonitor;
+mod watch;
+mod watches;
 
 pub const COMMANDS: &[CommandDef] = &[
     CommandDef {
         name: monitor::MONITOR,
         builder: monitor::monitor_builder,
         handler: |c, i| Box::pin(async move { monitor::monitor(c, i).await }),
+        autocomplete: Some(|c, i| Box::pin(async move { monitor::monitor_autocomplete(c, i).await })),
         re_register: false,
         whitelisted_servers: None,
-    }
+    },
+    CommandDef {
+        name: monitor_message::MONITOR_MESSAGE,
+        builder: monitor_message::monitor_message_builder,
+        handler: |c, i| Box::pin(async move { monitor_message::monitor_message(c, i).await }),
+        autocomplete: None,
+        re_register: false,
+        whitelisted_servers: None,
+    },
+    CommandDef {
+        name: monitor_playlist::MONITOR_PLAYLIST,
+        builder: monitor_playlist::monitor_playlist_builder,
+        handler: |c, i| Box::pin(async move { monitor_playlist::monitor_playlist(c, i).await }),
+        autocomplete: None,
+        re_register: false,
+        whitelisted_servers: None,
+    },
+    CommandDef {
+        name: unmonitor::UNMONITOR,
+        builder: unmonitor::unmonitor_builder,
+        handler: |c, i| Box::pin(async move { unmonitor::unmonitor(c, i).await }),
+        autocomplete: Some(|c, i| Box::pin(async move { watch::watched_artist_autocomplete(c, i).await })),
+        re_register: false,
+        whitelisted_servers: None,
+    },
+    CommandDef {
+        name: watches::WATCHES,
+        builder: watches::watches_builder,
+        handler: |c, i| Box::pin(async move { watches::watches(c, i).await }),
+        autocomplete: None,
+        re_register: false,
+        whitelisted_servers: None,
+    },
+    CommandDef {
+        name: watch::WATCH,
+        builder: watch::watch_builder,
+        handler: |c, i| Box::pin(async move { watch::watch(c, i).await }),
+        autocomplete: Some(|c, i| Box::pin(async move { watch::watched_artist_autocomplete(c, i).await })),
+        re_register: false,
+        whitelisted_servers: None,
+    },
+    CommandDef {
+        name: settings::SETTINGS,
+        builder: settings::settings_builder,
+        handler: |c, i| Box::pin(async move { settings::settings(c, i).await }),
+        autocomplete: None,
+        re_register: false,
+        whitelisted_servers: None,
+    },
+    CommandDef {
+        name: releases::RELEASES,
+        builder: releases::releases_builder,
+        handler: |c, i| Box::pin(async move { releases::releases(c, i).await }),
+        autocomplete: Some(|c, i| Box::pin(async move { releases::releases_autocomplete(c, i).await })),
+        re_register: false,
+        whitelisted_servers: None,
+    },
+    CommandDef {
+        name: alerts::ALERTS,
+        builder: alerts::alerts_builder,
+        handler: |c, i| Box::pin(async move { alerts::alerts(c, i).await }),
+        autocomplete: None,
+        re_register: false,
+        whitelisted_servers: None,
+    },
+    CommandDef {
+        name: follow::FOLLOW,
+        builder: follow::follow_builder,
+        handler: |c, i| Box::pin(async move { follow::follow(c, i).await }),
+        autocomplete: Some(|c, i| Box::pin(async move { monitor::monitor_autocomplete(c, i).await })),
+        re_register: false,
+        whitelisted_servers: None,
+    },
+    CommandDef {
+        name: unfollow::UNFOLLOW,
+        builder: unfollow::unfollow_builder,
+        handler: |c, i| Box::pin(async move { unfollow::unfollow(c, i).await }),
+        autocomplete: Some(|c, i| Box::pin(async move { follow::followed_artist_autocomplete(c, i).await })),
+        re_register: false,
+        whitelisted_servers: None,
+    },
+];
+
+pub const COMPONENTS: &[ComponentDef] = &[
+    ComponentDef {
+        prefix: watches::WATCHES_PAGE,
+        handler: |c, i| Box::pin(async move { watches::watches_page(c, i).await }),
+    },
+    ComponentDef {
+        prefix: releases::RELEASES_PAGE,
+        handler: |c, i| Box::pin(async move { releases::releases_page(c, i).await }),
+    },
+    ComponentDef {
+        prefix: bulk::BULK_WATCH,
+        handler: |c, i| Box::pin(async move { bulk::bulk_watch(c, i).await }),
+    },
+    ComponentDef {
+        prefix: monitor_message::WATCH_ARTIST,
+        handler: |c, i| Box::pin(async move { monitor_message::watch_artist(c, i).await }),
+    },
+    ComponentDef {
+        prefix: crate::monitor::FOLLOW_BUTTON,
+        handler: |c, i| Box::pin(async move { follow::follow_button(c, i).await }),
+    },
 ];
 
 static COMMAND_MAP: Lazy<DashMap<String, InteractionHandler>> = Lazy::new(|| {
@@ -24,9 +144,52 @@ static COMMAND_MAP: Lazy<DashMap<String, InteractionHandler>> = Lazy::new(|| {
     map
 });
 
+static AUTOCOMPLETE_MAP: Lazy<DashMap<String, AutocompleteHandler>> = Lazy::new(|| {
+    let map = DashMap::new();
+
+    for cmd in COMMANDS {
+        if let Some(handler) = cmd.autocomplete {
+            map.insert(cmd.name.to_string(), handler);
+        }
+    }
+
+    map
+});
+
+static COMPONENT_MAP: Lazy<DashMap<String, ComponentHandler>> = Lazy::new(|| {
+    let map = DashMap::new();
+
+    for component in COMPONENTS {
+        map.insert(component.prefix.to_string(), component.handler);
+    }
+
+    map
+});
+
 pub fn get_handler(command_name: &str) -> Option<InteractionHandler> {
     COMMAND_MAP
         .get(command_name)
         .as_ref()
         .map(|entry| *entry.value())
 }
+
+pub fn get_autocomplete_handler(command_name: &str) -> Option<AutocompleteHandler> {
+    AUTOCOMPLETE_MAP
+        .get(command_name)
+        .as_ref()
+        .map(|entry| *entry.value())
+}
+
+/// Offers to watch the artists of Spotify links posted in a server's link detection channels.
+pub async fn detect_links(ctx: Context, message: Message) -> anyhow::Result<()> {
+    monitor_message::detect_links(ctx, message).await
+}
+
+pub fn get_component_handler(custom_id: &str) -> Option<ComponentHandler> {
+    let prefix = custom_id.split(':').next().unwrap_or_default();
+
+    COMPONENT_MAP
+        .get(prefix)
+        .as_ref()
+        .map(|entry| *entry.value())
+}
@@ -1,175 +1,205 @@
 use evlog::meta;
-use once_cell::sync::Lazy;
-use regex::Regex;
+use rspotify::model::Id;
 use serenity::builder::CreateApplicationCommand;
 use serenity::client::Context;
-use serenity::http::{CacheHttp, Http};
+use serenity::model::id::ChannelId;
 use serenity::model::interactions::application_command::{ApplicationCommandInteraction, ApplicationCommandOptionType};
-use serenity::model::Permissions;
+use serenity::model::interactions::autocomplete::AutocompleteInteraction;
 use serenity::utils::MessageBuilder;
 
+use crate::commands::settings;
 use crate::db;
 use crate::handler::BotData;
-use crate::helpers::{command_opt, command_resp};
+use crate::helpers::{command_opt, command_resp, permissions};
 use crate::runtime::get_logger;
+use crate::spotify;
+use crate::spotify::release_types;
 
 pub const MONITOR: &str = "monitor";
 
-static MATCH_ARTIST_ID: Lazy<Regex> = Lazy::new(|| Regex::new(r#"https://open.spotify.com/artist/(\w+)"#).unwrap());
-
 pub fn monitor_builder(cmd: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
     cmd.name(MONITOR)
         .description("Monitor a new Spotify page")
         .create_option(|opt| opt
             .name("artist")
-            .description("The link to the artist to monitor (https://open.spotify.com/artist/...)")
+            .description("Start typing an artist's name, or paste a link (https://open.spotify.com/artist/...)")
             .required(true)
+            .set_autocomplete(true)
             .kind(ApplicationCommandOptionType::String))
         .create_option(|opt| opt
-            .name("market")
-            .description("An ISO Alpha-2 country code to monitor releases in (ex: US, GB, EU)")
-            .required(true)
+            .name("markets")
+            .description("Country codes or presets (ex: US; US, GB; EU; LATAM; NORDICS; default: the server's default)")
+            .required(false)
             .kind(ApplicationCommandOptionType::String))
         .create_option(|opt| opt
-            .name("channel-id")
-            .description("The ID of the channel to send alerts in; enable Developer Mode and right click channel to copy ID")
-            .required(true)
+            .name("channel")
+            .description("The channel to send alerts in (default: the server's default channel, or this channel)")
+            .required(false)
+            .channel_types(permissions::ALERT_CHANNEL_TYPES)
+            .kind(ApplicationCommandOptionType::Channel))
+        .create_option(|opt| opt
+            .name("release-types")
+            .description("Release types to alert on: album, single, compilation, appears_on (default: server default)")
+            .required(false)
             .kind(ApplicationCommandOptionType::String))
+        .create_option(|opt| opt
+            .name("mention-role")
+            .description("A role to mention in this artist's alerts")
+            .required(false)
+            .kind(ApplicationCommandOptionType::Role))
 }
 
 pub async fn monitor(ctx: Context, interaction: ApplicationCommandInteraction) -> anyhow::Result<()> {
     let artist = command_opt::find_required(&ctx, &interaction, command_opt::find_string_opt, "artist").await?.unwrap();
-    let market = command_opt::find_required(&ctx, &interaction, command_opt::find_string_opt, "market").await?.unwrap();
-    let channel = command_opt::find_required(&ctx, &interaction, command_opt::find_string_opt, "channel-id").await?.unwrap();
+    let market = command_opt::find_string_opt(&interaction.data.options, "markets");
+    let channel_id = command_opt::find_channel_opt(&interaction.data.options, "channel").map(|v| v.id);
+    let types_input = command_opt::find_string_opt(&interaction.data.options, "release-types");
+    let mention_role = command_opt::find_role_opt(&interaction.data.options, "mention-role");
 
     command_resp::reply_deferred_ack(&ctx, &interaction).await?;
 
-    let market = market.to_uppercase();
-
     //
 
-    let guild_id = match interaction.guild_id {
-        None => {
-            get_logger().info("Interaction was not used in a guild.", meta! {
-                "InteractionID" => interaction.id,
-            });
-            command_resp::reply_deferred_result(&ctx, &interaction, "Invalid channel ID; should be a positive number like 743962690627698758.").await.unwrap();
-            return Ok(());
-        }
+    let guild_id = match permissions::authorize_watch_manager(&ctx, &interaction, "/monitor").await? {
+        None => return Ok(()),
         Some(v) => v,
     };
 
+    let member_id = interaction.member.as_ref().unwrap();
+
     //
 
-    let member_id = interaction.member.as_ref().unwrap();
+    let data = ctx.data.read().await;
+    let data = data.get::<BotData>().unwrap();
 
-    let permissions = match member_id.permissions {
-        None => {
-            get_logger().info("Could not read interaction invoker's permissions.", meta! {
+    let guild_settings = db::model::get_guild_settings(data.db_client.conn(), *guild_id.as_u64()).await?;
+
+    let markets = match &market {
+        Some(market) => match spotify::markets::parse_markets(market) {
+            Ok(v) => v,
+            Err(e) => {
+                get_logger().info("Invalid markets passed to /monitor.", meta! {
+                    "InteractionID" => interaction.id,
+                    "GuildID" => guild_id,
+                    "Artist" => artist,
+                    "Markets" => market,
+                });
+                command_resp::reply_deferred_result(&ctx, &interaction, e).await.unwrap();
+                return Ok(());
+            }
+        },
+        None => match settings::default_markets(&ctx, guild_id, &guild_settings).await {
+            Some(v) => v,
+            None => {
+                command_resp::reply_deferred_result(&ctx, &interaction, "Pass the markets to monitor, or set a default with /settings default-markets.").await.unwrap();
+                return Ok(());
+            }
+        },
+    };
+    let market = markets.join(",");
+
+    let channel_id = channel_id
+        .or_else(|| guild_settings.default_alert_channel.map(ChannelId))
+        .unwrap_or(interaction.channel_id);
+
+    let album_groups = match types_input {
+        None => guild_settings.default_album_groups.clone().unwrap_or_else(release_types::default_release_types),
+        Some(v) => match release_types::parse_release_types(&v) {
+            Ok(v) => v,
+            Err(e) => {
+                command_resp::reply_deferred_result(&ctx, &interaction, e).await.unwrap();
+                return Ok(());
+            }
+        },
+    };
+
+    match permissions::check_alert_channel(&ctx.cache, guild_id, channel_id) {
+        Ok(()) => {}
+        Err(e) => {
+            get_logger().info("Bot cannot send alerts in the target channel.", meta! {
                 "InteractionID" => interaction.id,
                 "GuildID" => guild_id,
+                "ChannelID" => channel_id,
+                "Artist" => artist,
+                "Markets" => market,
             });
-            command_resp::reply_deferred_result(&ctx, &interaction, "Invalid channel ID; should be a positive number like 743962690627698758.").await.unwrap();
+            command_resp::reply_deferred_result(&ctx, &interaction, e.description(channel_id)).await.unwrap();
             return Ok(());
         }
-        Some(v) => v,
-    };
-
-    if !permissions.contains(Permissions::ADMINISTRATOR) {
-        get_logger().info("Non-administrator attempted to add watch.", meta! {
-            "InteractionID" => interaction.id,
-            "GuildID" => guild_id,
-        });
-        command_resp::reply_deferred_result(&ctx, &interaction, "Only members with the 'Administrator' permission may use /monitor.").await.unwrap();
-        return Ok(());
     }
 
     //
 
-    let channel_id = match channel.parse::<u64>() {
-        Ok(v) => v,
-        Err(_) => {
-            get_logger().info("Invalid channel ID passed to /monitor.", meta! {
+    let artist_details = match data.spotify_client.resolve_artist(&artist).await {
+        Ok(Some(v)) => v,
+        Ok(None) => {
+            get_logger().info("Unknown artist passed to /monitor.", meta! {
                 "InteractionID" => interaction.id,
                 "GuildID" => guild_id,
-                "ChannelID" => channel,
-                "ArtistURL" => artist,
-                "Market" => market,
+                "ChannelID" => channel_id,
+                "Artist" => artist,
+                "Markets" => market,
             });
-            command_resp::reply_deferred_result(&ctx, &interaction, "Invalid channel ID; should be a positive number like 743962690627698758.").await.unwrap();
+            command_resp::reply_deferred_result(&ctx, &interaction, "Could not find that artist on Spotify; pick one from the list or paste a link like `https://open.spotify.com/artist/...`").await.unwrap();
             return Ok(());
         }
-    };
-
-    let artist_id = match MATCH_ARTIST_ID.captures(&artist) {
-        None => {
-            get_logger().info("Invalid artist URL passed to /monitor.", meta! {
+        Err(e) => {
+            get_logger().error("Failed to look up artist passed to /monitor.", meta! {
                 "InteractionID" => interaction.id,
                 "GuildID" => guild_id,
                 "ChannelID" => channel_id,
-                "ArtistURL" => artist,
-                "Market" => market,
+                "Artist" => artist,
+                "Markets" => market,
+                "Error" => e,
             });
-            command_resp::reply_deferred_result(&ctx, &interaction, "Invalid artist link; should be like: `https://open.spotify.com/artist/...`").await.unwrap();
+            command_resp::reply_deferred_result(&ctx, &interaction, "Failed to look up that artist on Spotify.").await.unwrap();
             return Ok(());
         }
-        Some(v) => {
-            if v.len() != 2 {
-                get_logger().info("Invalid artist URL passed to /monitor.", meta! {
-                    "InteractionID" => interaction.id,
-                    "GuildID" => guild_id,
-                    "ChannelID" => channel_id,
-                    "ArtistURL" => artist,
-                    "Market" => market,
-                });
-                command_resp::reply_deferred_result(&ctx, &interaction, "Invalid artist link; should be like: `https://open.spotify.com/artist/...`").await.unwrap();
-                return Ok(());
-            }
-
-            v.get(1).unwrap().as_str()
-        }
     };
+    let artist_id = artist_details.id.id();
 
-    //
-
-    let can_send = try_send_message(ctx.http(), guild_id.as_u64(), &channel_id).await;
-
-    if !can_send {
-        get_logger().info("Failed to send test message in the target channel.", meta! {
+    if let Err(e) = crate::monitor::save_artist(&data.db_client, &artist_details).await {
+        get_logger().error("Failed to save artist details.", meta! {
             "InteractionID" => interaction.id,
             "GuildID" => guild_id,
-            "ChannelID" => channel_id,
-            "ArtistURL" => artist,
-            "Market" => market,
+            "ArtistID" => artist_id,
+            "Error" => e,
         });
-        command_resp::reply_deferred_result(&ctx, &interaction, format!(
-            "Failed to send a test message in the channel with ID `{}`; do I have permissions to send messages there? Is that channel in this server?",
-            channel_id
-        )).await.unwrap();
+
+        command_resp::reply_deferred_result(&ctx, &interaction, "Failed to save new artist watch.").await.unwrap();
         return Ok(());
     }
 
-    //
-
-    let data = ctx.data.read().await;
-    let data = data.get::<BotData>().unwrap();
-
     let watch = match db::model::add_watch(
         data.db_client.conn(),
         *member_id.user.id.as_u64(),
         *guild_id.as_u64(),
-        channel_id,
+        *channel_id.as_u64(),
         artist_id,
-        &market,
+        markets.clone(),
+        album_groups.clone(),
+        mention_role.as_ref().map(|v| *v.id.as_u64()),
     ).await {
-        Ok(v) => v,
+        Ok(Some(v)) => v,
+        Ok(None) => {
+            get_logger().info("Artist passed to /monitor is already watched.", meta! {
+                "InteractionID" => interaction.id,
+                "GuildID" => guild_id,
+                "ArtistID" => artist_id,
+            });
+
+            command_resp::reply_deferred_result(&ctx, &interaction, format!(
+                "This server is already monitoring **{}**; use /watch edit to change its channel, markets or release types.", artist_details.name
+            )).await.unwrap();
+            return Ok(());
+        }
         Err(e) => {
             get_logger().error("Failed to save new artist watch.", meta! {
                 "InteractionID" => interaction.id,
                 "GuildID" => guild_id,
                 "ChannelID" => channel_id,
-                "ArtistURL" => artist,
-                "Market" => market,
+                "Artist" => artist,
+                "Markets" => market,
                 "Error" => e,
             });
 
@@ -182,8 +212,8 @@ pub async fn monitor(ctx: Context, interaction: ApplicationCommandInteraction) -
         "InteractionID" => interaction.id,
         "GuildID" => guild_id,
         "ChannelID" => channel_id,
-        "ArtistURL" => artist,
-        "Market" => market,
+        "Artist" => artist,
+        "Markets" => market,
         "WatchID" => watch.id,
     });
 
@@ -198,10 +228,18 @@ pub async fn monitor(ctx: Context, interaction: ApplicationCommandInteraction) -
         });
 
         e.title("New release watch created");
+        e.url(format!("https://open.spotify.com/artist/{}", artist_id));
+        if let Some(image) = artist_details.images.first() {
+            e.thumbnail(&image.url);
+        }
 
-        e.field("Artist ID", artist_id, true);
-        e.field("Market", market, true);
+        e.field("Artist", &artist_details.name, true);
+        e.field("Markets", spotify::markets::describe_markets(&markets), true);
         e.field("Channel", MessageBuilder::new().channel(channel_id).build(), true);
+        e.field("Release types", release_types::describe_release_types(&album_groups), true);
+        if let Some(role) = &mention_role {
+            e.field("Mentions", format!("<@&{}>", role.id), true);
+        }
         e.field("ID", watch.id, true);
 
         e
@@ -210,26 +248,35 @@ pub async fn monitor(ctx: Context, interaction: ApplicationCommandInteraction) -
     Ok(())
 }
 
-async fn try_send_message(http: &Http, server_id: &u64, channel_id: &u64) -> bool {
-    let channel = match http.get_channel(*channel_id).await {
-        Ok(v) => v,
-        Err(_) => return false,
+pub async fn monitor_autocomplete(ctx: Context, interaction: AutocompleteInteraction) -> anyhow::Result<()> {
+    let input = match command_opt::find_focused_opt(&interaction.data.options) {
+        None => return Ok(()),
+        Some(v) => {
+            if v.name != "artist" { return Ok(()); }
+            v.value
+        }
     };
 
-    match channel.clone().guild() {
-        None => return false,
-        Some(v) => if v.guild_id.as_u64() != server_id {
-            return false;
+    let data = ctx.data.read().await;
+    let data = data.get::<BotData>().unwrap();
+
+    let artists = if input.trim().is_empty() {
+        Vec::new()
+    } else {
+        match spotify::parse_artist_id(&input) {
+            Some(id) => data.spotify_client.artist(&id).await.into_iter().collect(),
+            None => data.spotify_client.search_artists(input.trim(), 10).await?,
         }
     };
 
-    let channel_id = channel.clone().id();
-
-    match channel_id.send_message(http, |f| f.content("Test message; please delete.")).await {
-        Ok(v) => {
-            let _ = v.delete(http).await;
-            true
+    interaction.create_autocomplete_response(&ctx.http, |r| {
+        for artist in &artists {
+            let name = format!("{} ({} followers)", artist.name, artist.followers.total);
+            r.add_string_choice(name.chars().take(100).collect::<String>(), artist.id.id());
         }
-        Err(_) => false,
-    }
+
+        r
+    }).await?;
+
+    Ok(())
 }
@@ -0,0 +1,309 @@
+use evlog::meta;
+use rspotify::model::{FullArtist, Id};
+use serenity::builder::{CreateApplicationCommand, CreateComponents, CreateEmbed};
+use serenity::client::Context;
+use serenity::model::channel::Message;
+use serenity::model::id::{ChannelId, GuildId};
+use serenity::model::interactions::application_command::{ApplicationCommandInteraction, ApplicationCommandType, ResolvedTarget};
+use serenity::model::interactions::InteractionResponseType;
+use serenity::model::interactions::message_component::{ButtonStyle, MessageComponentInteraction};
+use serenity::utils::Color;
+
+use crate::commands::settings;
+use crate::db;
+use crate::db::schema::WatchedArtist;
+use crate::handler::BotData;
+use crate::helpers::{command_resp, permissions};
+use crate::runtime::get_logger;
+use crate::spotify;
+use crate::spotify::release_types;
+
+pub const MONITOR_MESSAGE: &str = "Monitor this artist";
+
+/// Custom ID prefix of the one-click watch buttons; the full ID is `watch-artist:<artist ID>`.
+pub const WATCH_ARTIST: &str = "watch-artist";
+
+/// An action row holds at most 5 buttons, so at most this many artists are offered per message.
+const MAX_ARTISTS: usize = 5;
+
+pub fn monitor_message_builder(cmd: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    cmd.name(MONITOR_MESSAGE)
+        .kind(ApplicationCommandType::Message)
+}
+
+pub async fn monitor_message(ctx: Context, interaction: ApplicationCommandInteraction) -> anyhow::Result<()> {
+    command_resp::reply_deferred_ack(&ctx, &interaction).await?;
+
+    let guild_id = match permissions::authorize_watch_manager(&ctx, &interaction, "\"Monitor this artist\"").await? {
+        None => return Ok(()),
+        Some(v) => v,
+    };
+
+    let message = match interaction.data.target() {
+        Some(ResolvedTarget::Message(v)) => v,
+        _ => return Ok(()),
+    };
+
+    // Links posted on their own are often only kept in the embed Discord unfurls for them.
+    let mut text = message.content.clone();
+    for embed in &message.embeds {
+        if let Some(url) = &embed.url {
+            text.push('\n');
+            text.push_str(url);
+        }
+    }
+
+    let mut links = spotify::find_links(&text);
+    if links.is_empty() {
+        command_resp::reply_deferred_result(&ctx, &interaction, "That message doesn't contain any Spotify artist, album or track links.").await.unwrap();
+        return Ok(());
+    }
+    links.truncate(MAX_ARTISTS);
+
+    //
+
+    let data = ctx.data.read().await;
+    let data = data.get::<BotData>().unwrap();
+
+    let mut artists = match data.spotify_client.link_artists(&links).await {
+        Ok(v) => v,
+        Err(e) => {
+            get_logger().error("Failed to resolve artists of Spotify links.", meta! {
+                "InteractionID" => interaction.id,
+                "GuildID" => guild_id,
+                "MessageID" => message.id,
+                "Error" => e,
+            });
+            command_resp::reply_deferred_result(&ctx, &interaction, "Failed to look up the links in that message on Spotify.").await.unwrap();
+            return Ok(());
+        }
+    };
+    artists.truncate(MAX_ARTISTS);
+
+    if artists.is_empty() {
+        command_resp::reply_deferred_result(&ctx, &interaction, "Could not find the links in that message on Spotify.").await.unwrap();
+        return Ok(());
+    }
+
+    let watched = db::model::list_server_watched_artists(data.db_client.conn(), *guild_id.as_u64()).await?;
+
+    let (embed, components) = build_prompt(&artists, &watched);
+
+    interaction.edit_original_interaction_response(&ctx.http, |r| r
+        .add_embed(embed)
+        .components(|c| {
+            *c = components;
+            c
+        })).await?;
+
+    Ok(())
+}
+
+/// Builds the embed listing the artists found in a message, and their watch buttons.
+#[must_use]
+pub fn build_prompt(artists: &[FullArtist], watched: &[WatchedArtist]) -> (CreateEmbed, CreateComponents) {
+    let mut e = CreateEmbed::default();
+
+    e.author(|a| {
+        a.name("Spotlit");
+        a.icon_url("https://i.imgur.com/iKUvWHR.png");
+
+        a
+    });
+
+    e.title("Monitor this artist?");
+    e.color(Color::from_rgb(30, 215, 96));
+    e.description(artists.iter()
+        .map(|v| {
+            let line = format!("[{}](https://open.spotify.com/artist/{})", v.name, v.id.id());
+            if watched.iter().any(|w| w.id_artist == v.id.id()) {
+                format!("{} · already monitored", line)
+            } else {
+                line
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n"));
+    if let Some(image) = artists.first().and_then(|v| v.images.first()) {
+        e.thumbnail(&image.url);
+    }
+    e.footer(|f| f.text("Watches use this server's default channel, markets and release types."));
+
+    (e, build_buttons(artists, watched))
+}
+
+/// Builds a watch button for each artist, disabled for artists the server already watches.
+#[must_use]
+fn build_buttons(artists: &[FullArtist], watched: &[WatchedArtist]) -> CreateComponents {
+    let mut c = CreateComponents::default();
+
+    c.create_action_row(|r| {
+        for artist in artists {
+            let is_watched = watched.iter().any(|w| w.id_artist == artist.id.id());
+
+            r.create_button(|b| b
+                .custom_id(format!("{}:{}", WATCH_ARTIST, artist.id.id()))
+                .label(format!("Watch {}", artist.name).chars().take(80).collect::<String>())
+                .style(ButtonStyle::Success)
+                .disabled(is_watched));
+        }
+
+        r
+    });
+
+    c
+}
+
+/// Offers watch buttons for the artists of Spotify links posted in one of the server's link
+/// detection channels. Message content is only matched against; it's never stored or logged.
+pub async fn detect_links(ctx: Context, message: Message) -> anyhow::Result<()> {
+    if message.author.bot {
+        return Ok(());
+    }
+
+    let guild_id = match message.guild_id {
+        None => return Ok(()),
+        Some(v) => v,
+    };
+
+    // Matched before any lookups, so messages without links cost nothing else.
+    let mut links = spotify::find_links(&message.content);
+    if links.is_empty() {
+        return Ok(());
+    }
+    links.truncate(MAX_ARTISTS);
+
+    let data = ctx.data.read().await;
+    let data = data.get::<BotData>().unwrap();
+
+    let settings = db::model::get_guild_settings(data.db_client.conn(), *guild_id.as_u64()).await?;
+    if !settings.link_channel_ids.contains(message.channel_id.as_u64()) {
+        return Ok(());
+    }
+
+    let watched = db::model::list_server_watched_artists(data.db_client.conn(), *guild_id.as_u64()).await?;
+
+    let mut artists = data.spotify_client.link_artists(&links).await?;
+    artists.retain(|v| !watched.iter().any(|w| w.id_artist == v.id.id()));
+    artists.truncate(MAX_ARTISTS);
+
+    if artists.is_empty() {
+        return Ok(());
+    }
+
+    let components = build_buttons(&artists, &watched);
+
+    message.channel_id.send_message(&ctx.http, |m| m
+        .content(if artists.len() == 1 { "Watch this artist?" } else { "Watch these artists?" })
+        .reference_message(&message)
+        .allowed_mentions(|a| a.empty_parse().replied_user(false))
+        .components(|c| {
+            *c = components;
+            c
+        })).await?;
+
+    get_logger().debug("Offered to watch artists of posted Spotify links.", meta! {
+        "GuildID" => guild_id,
+        "ChannelID" => message.channel_id,
+        "MessageID" => message.id,
+        "Artists" => artists.len(),
+    });
+
+    Ok(())
+}
+
+/// Handles the watch buttons; the custom ID is `watch-artist:<artist ID>`.
+pub async fn watch_artist(ctx: Context, interaction: MessageComponentInteraction) -> anyhow::Result<()> {
+    let artist_id = interaction.data.custom_id.split(':').nth(1).unwrap_or_default().to_owned();
+
+    let guild_id = match interaction.guild_id {
+        None => return Ok(()),
+        Some(v) => v,
+    };
+    let member = match &interaction.member {
+        None => return Ok(()),
+        Some(v) => v,
+    };
+
+    if let Some(reason) = permissions::check_watch_manager(&ctx, guild_id, member, "this button").await? {
+        interaction.create_interaction_response(&ctx.http, |r| r
+            .kind(InteractionResponseType::ChannelMessageWithSource)
+            .interaction_response_data(|d| d
+                .content(reason)
+                .ephemeral(true))).await?;
+        return Ok(());
+    }
+
+    interaction.create_interaction_response(&ctx.http, |r| r
+        .kind(InteractionResponseType::DeferredChannelMessageWithSource)).await?;
+
+    let content = create_watch(&ctx, guild_id, interaction.channel_id, *member.user.id.as_u64(), &artist_id).await?;
+
+    interaction.edit_original_interaction_response(&ctx.http, |r| r
+        .content(content)).await?;
+
+    Ok(())
+}
+
+/// Creates a watch with the server's defaults, and returns the message to reply with.
+async fn create_watch(ctx: &Context, guild_id: GuildId, channel_id: ChannelId, id_created_by: u64, artist_id: &str) -> anyhow::Result<String> {
+    let data = ctx.data.read().await;
+    let data = data.get::<BotData>().unwrap();
+
+    let guild_settings = db::model::get_guild_settings(data.db_client.conn(), *guild_id.as_u64()).await?;
+
+    let markets = match settings::default_markets(ctx, guild_id, &guild_settings).await {
+        Some(v) => v,
+        None => return Ok("This server has no default markets; set them with /settings default-markets, or use /monitor.".to_owned()),
+    };
+
+    let channel_id = guild_settings.default_alert_channel.map(ChannelId).unwrap_or(channel_id);
+    let album_groups = guild_settings.default_album_groups.clone().unwrap_or_else(release_types::default_release_types);
+
+    if let Err(e) = permissions::check_alert_channel(&ctx.cache, guild_id, channel_id) {
+        return Ok(e.description(channel_id));
+    }
+
+    let artist = match data.spotify_client.artist(artist_id).await {
+        Ok(v) => v,
+        Err(e) => {
+            get_logger().error("Failed to look up artist from watch button.", meta! {
+                "GuildID" => guild_id,
+                "ArtistID" => artist_id,
+                "Error" => e,
+            });
+            return Ok("Failed to look up that artist on Spotify.".to_owned());
+        }
+    };
+
+    crate::monitor::save_artist(&data.db_client, &artist).await?;
+
+    match db::model::add_watch(
+        data.db_client.conn(),
+        id_created_by,
+        *guild_id.as_u64(),
+        *channel_id.as_u64(),
+        artist_id,
+        markets.clone(),
+        album_groups.clone(),
+        None,
+    ).await? {
+        None => Ok(format!("This server is already monitoring **{}**.", artist.name)),
+        Some(watch) => {
+            get_logger().info("Saved new artist watch from watch button.", meta! {
+                "GuildID" => guild_id,
+                "ChannelID" => channel_id,
+                "ArtistID" => artist_id,
+                "WatchID" => watch.id,
+            });
+
+            Ok(format!(
+                "Now monitoring **{}** in {} for {}; alerts go to <#{}>. Use /watch edit to change this.",
+                artist.name,
+                spotify::markets::describe_markets(&markets),
+                release_types::describe_release_types(&album_groups),
+                channel_id,
+            ))
+        }
+    }
+}
@@ -0,0 +1,197 @@
+use evlog::meta;
+use rspotify::model::Id;
+use serenity::builder::{CreateApplicationCommand, CreateEmbed};
+use serenity::client::Context;
+use serenity::model::id::ChannelId;
+use serenity::model::interactions::application_command::{ApplicationCommandInteraction, ApplicationCommandOptionType};
+use serenity::utils::{Color, MessageBuilder};
+
+use crate::commands::bulk;
+use crate::commands::bulk::{BulkWatchEntry, PendingBulkWatch};
+use crate::commands::settings;
+use crate::db;
+use crate::handler::BotData;
+use crate::helpers::{command_opt, command_resp, permissions};
+use crate::runtime::get_logger;
+use crate::spotify;
+use crate::spotify::release_types;
+
+pub const MONITOR_PLAYLIST: &str = "monitor-playlist";
+
+pub fn monitor_playlist_builder(cmd: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    cmd.name(MONITOR_PLAYLIST)
+        .description("Monitor every artist on a Spotify playlist")
+        .create_option(|opt| opt
+            .name("playlist")
+            .description("A playlist link (https://open.spotify.com/playlist/...) or URI")
+            .required(true)
+            .kind(ApplicationCommandOptionType::String))
+        .create_option(|opt| opt
+            .name("markets")
+            .description("Country codes or presets (ex: US; US, GB; EU; LATAM; NORDICS; default: the server's default)")
+            .required(false)
+            .kind(ApplicationCommandOptionType::String))
+        .create_option(|opt| opt
+            .name("channel")
+            .description("The channel to send alerts in (default: the server's default channel, or this channel)")
+            .required(false)
+            .channel_types(permissions::ALERT_CHANNEL_TYPES)
+            .kind(ApplicationCommandOptionType::Channel))
+        .create_option(|opt| opt
+            .name("release-types")
+            .description("Release types to alert on: album, single, compilation, appears_on (default: server default)")
+            .required(false)
+            .kind(ApplicationCommandOptionType::String))
+}
+
+pub async fn monitor_playlist(ctx: Context, interaction: ApplicationCommandInteraction) -> anyhow::Result<()> {
+    let playlist = command_opt::find_required(&ctx, &interaction, command_opt::find_string_opt, "playlist").await?.unwrap();
+    let market = command_opt::find_string_opt(&interaction.data.options, "markets");
+    let channel_id = command_opt::find_channel_opt(&interaction.data.options, "channel").map(|v| v.id);
+    let types_input = command_opt::find_string_opt(&interaction.data.options, "release-types");
+
+    command_resp::reply_deferred_ack(&ctx, &interaction).await?;
+
+    //
+
+    let guild_id = match permissions::authorize_watch_manager(&ctx, &interaction, "/monitor-playlist").await? {
+        None => return Ok(()),
+        Some(v) => v,
+    };
+
+    let member_id = interaction.member.as_ref().unwrap();
+
+    //
+
+    let data = ctx.data.read().await;
+    let data = data.get::<BotData>().unwrap();
+
+    let guild_settings = db::model::get_guild_settings(data.db_client.conn(), *guild_id.as_u64()).await?;
+
+    let markets = match &market {
+        Some(market) => match spotify::markets::parse_markets(market) {
+            Ok(v) => v,
+            Err(e) => {
+                command_resp::reply_deferred_result(&ctx, &interaction, e).await.unwrap();
+                return Ok(());
+            }
+        },
+        None => match settings::default_markets(&ctx, guild_id, &guild_settings).await {
+            Some(v) => v,
+            None => {
+                command_resp::reply_deferred_result(&ctx, &interaction, "Pass the markets to monitor, or set a default with /settings default-markets.").await.unwrap();
+                return Ok(());
+            }
+        },
+    };
+    let market = markets.join(",");
+
+    let channel_id = channel_id
+        .or_else(|| guild_settings.default_alert_channel.map(ChannelId))
+        .unwrap_or(interaction.channel_id);
+
+    let album_groups = match types_input {
+        None => guild_settings.default_album_groups.clone().unwrap_or_else(release_types::default_release_types),
+        Some(v) => match release_types::parse_release_types(&v) {
+            Ok(v) => v,
+            Err(e) => {
+                command_resp::reply_deferred_result(&ctx, &interaction, e).await.unwrap();
+                return Ok(());
+            }
+        },
+    };
+
+    match permissions::check_alert_channel(&ctx.cache, guild_id, channel_id) {
+        Ok(()) => {}
+        Err(e) => {
+            command_resp::reply_deferred_result(&ctx, &interaction, e.description(channel_id)).await.unwrap();
+            return Ok(());
+        }
+    }
+
+    let playlist_id = match spotify::parse_playlist_id(&playlist) {
+        None => {
+            command_resp::reply_deferred_result(&ctx, &interaction, "Invalid playlist; paste a link like `https://open.spotify.com/playlist/...`").await.unwrap();
+            return Ok(());
+        }
+        Some(v) => v,
+    };
+
+    //
+
+    let artists = match data.spotify_client.playlist_artists(&playlist_id).await {
+        Ok(v) => v,
+        Err(e) => {
+            get_logger().error("Failed to read playlist passed to /monitor-playlist.", meta! {
+                "InteractionID" => interaction.id,
+                "GuildID" => guild_id,
+                "PlaylistID" => playlist_id,
+                "Error" => e,
+            });
+            command_resp::reply_deferred_result(&ctx, &interaction, "Failed to read that playlist from Spotify; make sure it's public.").await.unwrap();
+            return Ok(());
+        }
+    };
+
+    if artists.is_empty() {
+        command_resp::reply_deferred_result(&ctx, &interaction, "That playlist has no artists to monitor.").await.unwrap();
+        return Ok(());
+    }
+
+    let entries = artists.into_iter()
+        .filter_map(|v| Some(BulkWatchEntry {
+            id_artist: v.id?.id().to_owned(),
+            name: v.name,
+            id_alert_channel: *channel_id.as_u64(),
+            markets: markets.clone(),
+            album_groups: album_groups.clone(),
+            id_mention_role: None,
+            best_match_for: None,
+        }))
+        .collect::<Vec<_>>();
+    let names = entries.iter().map(|v| v.name.clone()).collect::<Vec<_>>();
+
+    get_logger().info("Staged playlist artist watches.", meta! {
+        "InteractionID" => interaction.id,
+        "GuildID" => guild_id,
+        "ChannelID" => channel_id,
+        "PlaylistID" => playlist_id,
+        "Markets" => market,
+        "Artists" => entries.len(),
+    });
+
+    let components = bulk::stage(*interaction.id.as_u64(), PendingBulkWatch::new(
+        *guild_id.as_u64(),
+        *member_id.user.id.as_u64(),
+        entries,
+        Vec::new(),
+    ));
+
+    //
+
+    let mut e = CreateEmbed::default();
+
+    e.author(|a| {
+        a.name("Spotlit");
+        a.icon_url("https://i.imgur.com/iKUvWHR.png");
+
+        a
+    });
+
+    e.title(format!("Monitor {} artists from this playlist?", names.len()));
+    e.url(format!("https://open.spotify.com/playlist/{}", playlist_id));
+    e.color(Color::from_rgb(30, 215, 96));
+    e.description(bulk::join_truncated(&names, 4096));
+    e.field("Markets", spotify::markets::describe_markets(&markets), true);
+    e.field("Channel", MessageBuilder::new().channel(channel_id).build(), true);
+    e.field("Release types", release_types::describe_release_types(&album_groups), true);
+
+    interaction.edit_original_interaction_response(&ctx.http, |r| r
+        .add_embed(e)
+        .components(|c| {
+            *c = components;
+            c
+        })).await?;
+
+    Ok(())
+}
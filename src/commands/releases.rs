@@ -0,0 +1,294 @@
+use serenity::builder::{CreateApplicationCommand, CreateComponents, CreateEmbed};
+use serenity::client::Context;
+use serenity::model::interactions::application_command::{ApplicationCommandInteraction, ApplicationCommandOptionType};
+use serenity::model::interactions::autocomplete::AutocompleteInteraction;
+use serenity::model::interactions::message_component::{ButtonStyle, MessageComponentInteraction};
+use serenity::utils::Color;
+
+use crate::db;
+use crate::db::schema::ArtistRelease;
+use crate::handler::BotData;
+use crate::helpers::{command_opt, command_resp};
+use crate::spotify;
+use crate::spotify::{markets, release_types};
+
+pub const RELEASES: &str = "releases";
+pub const RELEASES_PAGE: &str = "releases-page";
+
+/// Releases per page, each shown as its own embed with its cover art.
+const PAGE_SIZE: usize = 5;
+
+/// Releases available in more markets than this show a market count instead of the list.
+const MAX_LISTED_MARKETS: usize = 10;
+
+pub fn releases_builder(cmd: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    cmd.name(RELEASES)
+        .description("Browse the releases Spotlit has recorded for an artist")
+        .create_option(|opt| opt
+            .name("artist")
+            .description("Start typing the name of a monitored or followed artist")
+            .required(true)
+            .set_autocomplete(true)
+            .kind(ApplicationCommandOptionType::String))
+        .create_option(|opt| {
+            opt.name("type")
+                .description("Only show releases of this type")
+                .required(false)
+                .kind(ApplicationCommandOptionType::String);
+
+            for (value, name) in release_types::RELEASE_TYPES {
+                opt.add_string_choice(name, value);
+            }
+
+            opt
+        })
+        .create_option(|opt| opt
+            .name("year")
+            .description("Only show releases from this year")
+            .required(false)
+            .kind(ApplicationCommandOptionType::Integer))
+}
+
+/// The artist and filters a page was requested for, carried in the page buttons' custom IDs.
+struct ReleasesQuery {
+    id_artist: String,
+    album_group: Option<String>,
+    year: Option<i32>,
+}
+
+impl ReleasesQuery {
+    /// Builds the custom ID of a page button: `releases-page:<artist ID>:<type>:<year>:<page>`,
+    /// with unset filters left empty.
+    fn custom_id(&self, page_num: usize) -> String {
+        format!(
+            "{}:{}:{}:{}:{}",
+            RELEASES_PAGE,
+            self.id_artist,
+            self.album_group.as_deref().unwrap_or_default(),
+            self.year.map(|v| v.to_string()).unwrap_or_default(),
+            page_num,
+        )
+    }
+
+    fn from_custom_id(custom_id: &str) -> Option<(Self, usize)> {
+        let mut parts = custom_id.split(':').skip(1);
+
+        let id_artist = parts.next()?.to_owned();
+        let album_group = parts.next().filter(|v| !v.is_empty()).map(|v| v.to_owned());
+        let year = parts.next().and_then(|v| v.parse::<i32>().ok());
+        let page_num = parts.next().and_then(|v| v.parse::<usize>().ok()).unwrap_or(0);
+
+        Some((Self { id_artist, album_group, year }, page_num))
+    }
+}
+
+pub async fn releases(ctx: Context, interaction: ApplicationCommandInteraction) -> anyhow::Result<()> {
+    let artist = command_opt::find_required(&ctx, &interaction, command_opt::find_string_opt, "artist").await?.unwrap();
+    let album_group = command_opt::find_string_opt(&interaction.data.options, "type");
+    let year = command_opt::find_integer_opt(&interaction.data.options, "year").map(|v| v as i32);
+
+    let id_server = interaction.guild_id.map(|v| *v.as_u64());
+    let id_user = *interaction.user.id.as_u64();
+
+    let artist = {
+        let data = ctx.data.read().await;
+        let data = data.get::<BotData>().unwrap();
+
+        match spotify::parse_artist_id(&artist) {
+            Some(id) => db::model::get_artist(data.db_client.conn(), &id, id_server, id_user).await?,
+            None => db::model::search_stored_artists(data.db_client.conn(), artist.trim(), id_server, id_user, 1).await?.into_iter().next(),
+        }
+    };
+
+    let artist = match artist {
+        None => {
+            command_resp::reply(&ctx, &interaction, |r| r
+                .content("I haven't recorded any releases for that artist; you can browse the artists this server monitors and the artists you follow.")).await?;
+            return Ok(());
+        }
+        Some(v) => v,
+    };
+
+    let query = ReleasesQuery {
+        id_artist: artist.id_artist.clone(),
+        album_group,
+        year,
+    };
+
+    let page = load_page(&ctx, &query, id_server, id_user, 0).await?;
+
+    command_resp::reply(&ctx, &interaction, |r| match page {
+        None => r.content(format!("No recorded releases of **{}** match those filters.", artist.name)),
+        Some(page) => r
+            .set_embeds(page.embeds)
+            .set_components(page.components),
+    }).await?;
+
+    Ok(())
+}
+
+/// Handles the Previous/Next buttons; see `ReleasesQuery::custom_id` for the custom ID.
+pub async fn releases_page(ctx: Context, interaction: MessageComponentInteraction) -> anyhow::Result<()> {
+    let (query, page_num) = match ReleasesQuery::from_custom_id(&interaction.data.custom_id) {
+        None => return Ok(()),
+        Some(v) => v,
+    };
+
+    let page = load_page(&ctx, &query, interaction.guild_id.map(|v| *v.as_u64()), *interaction.user.id.as_u64(), page_num).await?;
+
+    command_resp::update_message(&ctx, &interaction, |r| match page {
+        None => r
+            .content("No recorded releases match those filters anymore.")
+            .set_embeds(Vec::new())
+            .set_components(CreateComponents::default()),
+        Some(page) => r
+            .set_embeds(page.embeds)
+            .set_components(page.components),
+    }).await?;
+
+    Ok(())
+}
+
+/// Autocompletes the `artist` option with the recorded artists the server monitors or the user
+/// follows.
+pub async fn releases_autocomplete(ctx: Context, interaction: AutocompleteInteraction) -> anyhow::Result<()> {
+    let input = match command_opt::find_focused_opt(&interaction.data.options) {
+        None => return Ok(()),
+        Some(v) => {
+            if v.name != "artist" { return Ok(()); }
+            v.value
+        }
+    };
+
+    let data = ctx.data.read().await;
+    let data = data.get::<BotData>().unwrap();
+
+    let artists = db::model::search_stored_artists(
+        data.db_client.conn(), input.trim(), interaction.guild_id.map(|v| *v.as_u64()), *interaction.user.id.as_u64(), 25,
+    ).await?;
+
+    interaction.create_autocomplete_response(&ctx.http, |r| {
+        for artist in &artists {
+            r.add_string_choice(artist.name.chars().take(100).collect::<String>(), &artist.id_artist);
+        }
+
+        r
+    }).await?;
+
+    Ok(())
+}
+
+struct ReleasesPage {
+    embeds: Vec<CreateEmbed>,
+    components: CreateComponents,
+}
+
+/// Loads a page of releases, if the artist is one the server monitors or the user follows; the
+/// artist ID of a page button can't be trusted.
+async fn load_page(ctx: &Context, query: &ReleasesQuery, id_server: Option<u64>, id_user: u64, page_num: usize) -> anyhow::Result<Option<ReleasesPage>> {
+    let data = ctx.data.read().await;
+    let data = data.get::<BotData>().unwrap();
+
+    let artist = match db::model::get_artist(data.db_client.conn(), &query.id_artist, id_server, id_user).await? {
+        None => return Ok(None),
+        Some(v) => v,
+    };
+
+    let total = db::model::count_artist_releases(
+        data.db_client.conn(), &query.id_artist, query.album_group.as_deref(), query.year,
+    ).await? as usize;
+    if total == 0 {
+        return Ok(None);
+    }
+
+    let page_count = (total + PAGE_SIZE - 1) / PAGE_SIZE;
+    let page_num = page_num.min(page_count - 1);
+
+    let releases = db::model::list_artist_releases(
+        data.db_client.conn(),
+        &query.id_artist,
+        query.album_group.as_deref(),
+        query.year,
+        PAGE_SIZE as i64,
+        (page_num * PAGE_SIZE) as i64,
+    ).await?;
+
+    let mut embeds = releases.iter().map(build_embed).collect::<Vec<_>>();
+
+    if let Some(first) = embeds.first_mut() {
+        first.author(|a| {
+            a.name(format!("Releases of {}", artist.name));
+            a.url(format!("https://open.spotify.com/artist/{}", artist.id_artist));
+            if let Some(image_url) = &artist.image_url {
+                a.icon_url(image_url);
+            }
+
+            a
+        });
+    }
+    if let Some(last) = embeds.last_mut() {
+        last.footer(|f| f.text(format!("Page {} of {} · {}", page_num + 1, page_count, describe_filters(query, total))));
+    }
+
+    Ok(Some(ReleasesPage {
+        embeds,
+        components: build_components(query, page_num, page_count),
+    }))
+}
+
+fn build_embed(release: &ArtistRelease) -> CreateEmbed {
+    let mut e = CreateEmbed::default();
+
+    e.title(&release.name);
+    e.url(format!("https://open.spotify.com/album/{}", release.id_release));
+    e.color(Color::from_rgb(30, 215, 96));
+    e.thumbnail(&release.image_url);
+    e.description(format!("by {}", release.artist_names.join(", ")));
+
+    e.field("Type", release_types::display_name(&release.album_group), true);
+    e.field("Released", &release.release_date, true);
+    e.field("Markets", if release.available_markets.is_empty() {
+        "None; no longer available".to_owned()
+    } else if release.available_markets.len() > MAX_LISTED_MARKETS {
+        format!("{} markets", release.available_markets.len())
+    } else {
+        markets::describe_markets(&release.available_markets)
+    }, true);
+
+    e
+}
+
+fn describe_filters(query: &ReleasesQuery, total: usize) -> String {
+    let mut result = format!("{} {}", total, if total == 1 { "release" } else { "releases" });
+
+    if let Some(album_group) = &query.album_group {
+        result.push_str(&format!(" · {}", release_types::display_name(album_group)));
+    }
+    if let Some(year) = query.year {
+        result.push_str(&format!(" · {}", year));
+    }
+
+    result
+}
+
+fn build_components(query: &ReleasesQuery, page_num: usize, page_count: usize) -> CreateComponents {
+    let mut c = CreateComponents::default();
+
+    if page_count <= 1 {
+        return c;
+    }
+
+    c.create_action_row(|r| r
+        .create_button(|b| b
+            .custom_id(query.custom_id(page_num.saturating_sub(1)))
+            .label("Previous")
+            .style(ButtonStyle::Secondary)
+            .disabled(page_num == 0))
+        .create_button(|b| b
+            .custom_id(query.custom_id(page_num + 1))
+            .label("Next")
+            .style(ButtonStyle::Secondary)
+            .disabled(page_num + 1 >= page_count)));
+
+    c
+}
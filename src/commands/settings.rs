@@ -0,0 +1,365 @@
+use evlog::meta;
+use serenity::builder::{CreateApplicationCommand, CreateEmbed};
+use serenity::client::Context;
+use serenity::model::id::GuildId;
+use serenity::model::interactions::application_command::{ApplicationCommandInteraction, ApplicationCommandOptionType};
+use serenity::model::Permissions;
+use serenity::utils::Color;
+
+use crate::db;
+use crate::db::schema::GuildSettings;
+use crate::handler::BotData;
+use crate::helpers::{command_opt, command_resp, permissions};
+use crate::monitor::{EMBED_STYLE_COMPACT, EMBED_STYLE_FULL};
+use crate::runtime::get_logger;
+use crate::spotify;
+use crate::spotify::release_types;
+
+pub const SETTINGS: &str = "settings";
+
+pub fn settings_builder(cmd: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    cmd.name(SETTINGS)
+        .description("Configure Spotlit for this server")
+        .create_option(|sub| sub
+            .name("show")
+            .description("Show this server's settings")
+            .kind(ApplicationCommandOptionType::SubCommand))
+        .create_option(|sub| sub
+            .name("default-channel")
+            .description("Set the channel /monitor sends alerts to when none is given")
+            .kind(ApplicationCommandOptionType::SubCommand)
+            .create_sub_option(|opt| opt
+                .name("channel")
+                .description("The default alert channel (leave out to use the channel /monitor is run in)")
+                .required(false)
+                .channel_types(permissions::ALERT_CHANNEL_TYPES)
+                .kind(ApplicationCommandOptionType::Channel)))
+        .create_option(|sub| sub
+            .name("default-markets")
+            .description("Set the markets /monitor uses when none are given")
+            .kind(ApplicationCommandOptionType::SubCommand)
+            .create_sub_option(|opt| opt
+                .name("markets")
+                .description("Country codes or presets (leave out to use this server's language)")
+                .required(false)
+                .kind(ApplicationCommandOptionType::String)))
+        .create_option(|sub| sub
+            .name("default-release-types")
+            .description("Set the release types /monitor uses when none are given")
+            .kind(ApplicationCommandOptionType::SubCommand)
+            .create_sub_option(|opt| opt
+                .name("release-types")
+                .description("album, single, compilation, appears_on (leave out for album, single, compilation)")
+                .required(false)
+                .kind(ApplicationCommandOptionType::String)))
+        .create_option(|sub| sub
+            .name("embed-style")
+            .description("Choose how release alerts look")
+            .kind(ApplicationCommandOptionType::SubCommand)
+            .create_sub_option(|opt| opt
+                .name("style")
+                .description("The alert style")
+                .required(true)
+                .add_string_choice("Full: large cover art and every detail", EMBED_STYLE_FULL)
+                .add_string_choice("Compact: small cover art and one line of details", EMBED_STYLE_COMPACT)
+                .kind(ApplicationCommandOptionType::String)))
+        .create_option(|sub| sub
+            .name("add-manager-role")
+            .description("Let members with a role manage watches")
+            .kind(ApplicationCommandOptionType::SubCommand)
+            .create_sub_option(|opt| opt
+                .name("role")
+                .description("The role to allow")
+                .required(true)
+                .kind(ApplicationCommandOptionType::Role)))
+        .create_option(|sub| sub
+            .name("remove-manager-role")
+            .description("Stop letting members with a role manage watches")
+            .kind(ApplicationCommandOptionType::SubCommand)
+            .create_sub_option(|opt| opt
+                .name("role")
+                .description("The role to remove")
+                .required(true)
+                .kind(ApplicationCommandOptionType::Role)))
+        .create_option(|sub| sub
+            .name("add-link-channel")
+            .description("Offer to watch the artists of Spotify links posted in a channel")
+            .kind(ApplicationCommandOptionType::SubCommand)
+            .create_sub_option(|opt| opt
+                .name("channel")
+                .description("The channel to watch for links")
+                .required(true)
+                .channel_types(permissions::ALERT_CHANNEL_TYPES)
+                .kind(ApplicationCommandOptionType::Channel)))
+        .create_option(|sub| sub
+            .name("remove-link-channel")
+            .description("Stop offering to watch the artists of Spotify links posted in a channel")
+            .kind(ApplicationCommandOptionType::SubCommand)
+            .create_sub_option(|opt| opt
+                .name("channel")
+                .description("The channel to stop watching for links")
+                .required(true)
+                .channel_types(permissions::ALERT_CHANNEL_TYPES)
+                .kind(ApplicationCommandOptionType::Channel)))
+        .create_option(|sub| sub
+            .name("manager-permission")
+            .description("Let members with a permission manage watches")
+            .kind(ApplicationCommandOptionType::SubCommand)
+            .create_sub_option(|opt| {
+                opt.name("permission")
+                    .description("The permission to allow, or none")
+                    .required(true)
+                    .kind(ApplicationCommandOptionType::String)
+                    .add_string_choice("None", "none");
+
+                for (value, name, _) in permissions::MANAGER_PERMISSIONS {
+                    opt.add_string_choice(name, value);
+                }
+
+                opt
+            }))
+}
+
+pub async fn settings(ctx: Context, interaction: ApplicationCommandInteraction) -> anyhow::Result<()> {
+    command_resp::reply_deferred_ack(&ctx, &interaction).await?;
+
+    let guild_id = match authorize(&ctx, &interaction).await? {
+        None => return Ok(()),
+        Some(v) => v,
+    };
+
+    let data = ctx.data.read().await;
+    let data = data.get::<BotData>().unwrap();
+
+    let settings = db::model::get_guild_settings(data.db_client.conn(), *guild_id.as_u64()).await?;
+    let opts = command_opt::command_options(&interaction);
+
+    let result = match command_opt::find_subcommand(&interaction) {
+        Some("default-channel") => match command_opt::find_channel_opt(opts, "channel") {
+            None => {
+                db::model::set_guild_default_alert_channel(data.db_client.conn(), *guild_id.as_u64(), None).await?;
+                "/monitor now sends alerts to the channel it's run in unless another is given.".to_owned()
+            }
+            Some(channel) => {
+                if let Err(e) = permissions::check_alert_channel(&ctx.cache, guild_id, channel.id) {
+                    command_resp::reply_deferred_result(&ctx, &interaction, e.description(channel.id)).await.unwrap();
+                    return Ok(());
+                }
+
+                db::model::set_guild_default_alert_channel(data.db_client.conn(), *guild_id.as_u64(), Some(*channel.id.as_u64())).await?;
+                format!("/monitor now sends alerts to <#{}> unless another channel is given.", channel.id)
+            }
+        },
+        Some("default-markets") => match command_opt::find_string_opt(opts, "markets") {
+            None => {
+                db::model::set_guild_default_markets(data.db_client.conn(), *guild_id.as_u64(), None).await?;
+                "/monitor now uses the market of this server's language unless markets are given.".to_owned()
+            }
+            Some(input) => match spotify::markets::parse_markets(&input) {
+                Ok(markets) => {
+                    let description = spotify::markets::describe_markets(&markets);
+                    db::model::set_guild_default_markets(data.db_client.conn(), *guild_id.as_u64(), Some(markets)).await?;
+                    format!("/monitor now uses {} unless markets are given.", description)
+                }
+                Err(e) => e,
+            },
+        },
+        Some("default-release-types") => match command_opt::find_string_opt(opts, "release-types") {
+            None => {
+                db::model::set_guild_default_album_groups(data.db_client.conn(), *guild_id.as_u64(), None).await?;
+                format!("/monitor now alerts on {} unless release types are given.", release_types::describe_release_types(&release_types::default_release_types()))
+            }
+            Some(input) => match release_types::parse_release_types(&input) {
+                Ok(album_groups) => {
+                    let description = release_types::describe_release_types(&album_groups);
+                    db::model::set_guild_default_album_groups(data.db_client.conn(), *guild_id.as_u64(), Some(album_groups)).await?;
+                    format!("/monitor now alerts on {} unless release types are given.", description)
+                }
+                Err(e) => e,
+            },
+        },
+        Some("embed-style") => {
+            let style = command_opt::find_string_opt(opts, "style").unwrap_or_else(|| EMBED_STYLE_FULL.to_owned());
+            db::model::set_guild_embed_style(data.db_client.conn(), *guild_id.as_u64(), &style).await?;
+
+            format!("Release alerts now use the {} style.", style)
+        }
+        Some("add-manager-role") => {
+            let role = command_opt::find_role_opt(opts, "role").unwrap();
+
+            if settings.manager_role_ids.contains(role.id.as_u64()) {
+                format!("Members with the <@&{}> role can already manage watches.", role.id)
+            } else {
+                let mut manager_role_ids = settings.manager_role_ids.clone();
+                manager_role_ids.push(*role.id.as_u64());
+                db::model::set_guild_manager_roles(data.db_client.conn(), *guild_id.as_u64(), &manager_role_ids).await?;
+
+                format!("Members with the <@&{}> role can now manage watches.", role.id)
+            }
+        }
+        Some("remove-manager-role") => {
+            let role = command_opt::find_role_opt(opts, "role").unwrap();
+
+            if settings.manager_role_ids.contains(role.id.as_u64()) {
+                let manager_role_ids = settings.manager_role_ids.iter()
+                    .copied()
+                    .filter(|v| v != role.id.as_u64())
+                    .collect::<Vec<_>>();
+                db::model::set_guild_manager_roles(data.db_client.conn(), *guild_id.as_u64(), &manager_role_ids).await?;
+
+                format!("Members with the <@&{}> role can no longer manage watches.", role.id)
+            } else {
+                format!("The <@&{}> role is not a manager role.", role.id)
+            }
+        }
+        Some("add-link-channel") => {
+            let channel = command_opt::find_channel_opt(opts, "channel").unwrap();
+
+            if !data.message_content {
+                "Link detection isn't available on this bot: whoever runs it hasn't turned on reading message content.".to_owned()
+            } else if settings.link_channel_ids.contains(channel.id.as_u64()) {
+                format!("Spotify links posted in <#{}> already get a watch prompt.", channel.id)
+            } else {
+                let mut link_channel_ids = settings.link_channel_ids.clone();
+                link_channel_ids.push(*channel.id.as_u64());
+                db::model::set_guild_link_channels(data.db_client.conn(), *guild_id.as_u64(), &link_channel_ids).await?;
+
+                format!("Spotify links posted in <#{}> now get a watch prompt.", channel.id)
+            }
+        }
+        Some("remove-link-channel") => {
+            let channel = command_opt::find_channel_opt(opts, "channel").unwrap();
+
+            if settings.link_channel_ids.contains(channel.id.as_u64()) {
+                let link_channel_ids = settings.link_channel_ids.iter()
+                    .copied()
+                    .filter(|v| v != channel.id.as_u64())
+                    .collect::<Vec<_>>();
+                db::model::set_guild_link_channels(data.db_client.conn(), *guild_id.as_u64(), &link_channel_ids).await?;
+
+                format!("Spotify links posted in <#{}> no longer get a watch prompt.", channel.id)
+            } else {
+                format!("Link detection is not on in <#{}>.", channel.id)
+            }
+        }
+        Some("manager-permission") => {
+            let value = command_opt::find_string_opt(opts, "permission").unwrap_or_default();
+
+            match permissions::MANAGER_PERMISSIONS.iter().find(|(v, _, _)| *v == value) {
+                None => {
+                    db::model::set_guild_manager_permissions(data.db_client.conn(), *guild_id.as_u64(), 0).await?;
+                    "Permissions other than 'Administrator' no longer let members manage watches.".to_owned()
+                }
+                Some((_, name, permission)) => {
+                    db::model::set_guild_manager_permissions(data.db_client.conn(), *guild_id.as_u64(), permission.bits()).await?;
+                    format!("Members with the '{}' permission can now manage watches.", name)
+                }
+            }
+        }
+        _ => {
+            let locale = ctx.cache.guild(guild_id).map(|v| v.preferred_locale);
+
+            interaction.edit_original_interaction_response(&ctx.http, |r| r
+                .add_embed(build_embed(&settings, locale.as_deref()))).await?;
+            return Ok(());
+        }
+    };
+
+    get_logger().info("Changed server settings.", meta! {
+        "InteractionID" => interaction.id,
+        "GuildID" => guild_id,
+        "Subcommand" => command_opt::find_subcommand(&interaction).unwrap_or_default(),
+    });
+
+    command_resp::reply_deferred_result(&ctx, &interaction, result).await.unwrap();
+
+    Ok(())
+}
+
+fn build_embed(settings: &GuildSettings, locale: Option<&str>) -> CreateEmbed {
+    let mut e = CreateEmbed::default();
+
+    e.author(|a| {
+        a.name("Spotlit");
+        a.icon_url("https://i.imgur.com/iKUvWHR.png");
+
+        a
+    });
+
+    e.title("Server settings");
+    e.color(Color::from_rgb(30, 215, 96));
+    e.field("Default channel", settings.default_alert_channel.map_or(
+        "The channel /monitor is run in".to_owned(),
+        |v| format!("<#{}>", v),
+    ), true);
+    e.field("Default markets", match (&settings.default_markets, locale.and_then(spotify::markets::market_from_locale)) {
+        (Some(v), _) => spotify::markets::describe_markets(v),
+        (None, Some(v)) => format!("{} (from this server's language)", v),
+        (None, None) => "None; /monitor needs markets".to_owned(),
+    }, true);
+    e.field("Default release types", release_types::describe_release_types(
+        &settings.default_album_groups.clone().unwrap_or_else(release_types::default_release_types)
+    ), true);
+    e.field("Embed style", &settings.embed_style, true);
+    e.field("Link detection", if settings.link_channel_ids.is_empty() {
+        "Off".to_owned()
+    } else {
+        settings.link_channel_ids.iter()
+            .map(|v| format!("<#{}>", v))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }, true);
+    e.field("Who can manage watches", permissions::describe_managers(settings), false);
+
+    e
+}
+
+/// Returns the markets to monitor when a command's markets option is left out: the server's
+/// default markets, or else the market of the server's preferred locale.
+pub async fn default_markets(ctx: &Context, guild_id: GuildId, settings: &GuildSettings) -> Option<Vec<String>> {
+    if let Some(v) = &settings.default_markets {
+        return Some(v.clone());
+    }
+
+    let locale = ctx.cache.guild(guild_id)?.preferred_locale;
+    spotify::markets::market_from_locale(&locale).map(|v| vec![v])
+}
+
+/// Settings decide who may manage watches, so only administrators may change them.
+async fn authorize(ctx: &Context, interaction: &ApplicationCommandInteraction) -> anyhow::Result<Option<GuildId>> {
+    let guild_id = match interaction.guild_id {
+        None => {
+            get_logger().info("Interaction was not used in a guild.", meta! {
+                "InteractionID" => interaction.id,
+            });
+            command_resp::reply_deferred_result(ctx, interaction, "/settings can only be used in a server.").await.unwrap();
+            return Ok(None);
+        }
+        Some(v) => v,
+    };
+
+    let member_id = interaction.member.as_ref().unwrap();
+
+    let permissions = match member_id.permissions {
+        None => {
+            get_logger().info("Could not read interaction invoker's permissions.", meta! {
+                "InteractionID" => interaction.id,
+                "GuildID" => guild_id,
+            });
+            command_resp::reply_deferred_result(ctx, interaction, "Could not read your permissions in this server.").await.unwrap();
+            return Ok(None);
+        }
+        Some(v) => v,
+    };
+
+    if !permissions.contains(Permissions::ADMINISTRATOR) {
+        get_logger().info("Non-administrator attempted to change settings.", meta! {
+            "InteractionID" => interaction.id,
+            "GuildID" => guild_id,
+        });
+        command_resp::reply_deferred_result(ctx, interaction, "Only members with the 'Administrator' permission may use /settings.").await.unwrap();
+        return Ok(None);
+    }
+
+    Ok(Some(guild_id))
+}
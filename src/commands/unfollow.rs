@@ -0,0 +1,61 @@
+use evlog::meta;
+use serenity::builder::CreateApplicationCommand;
+use serenity::client::Context;
+use serenity::model::interactions::application_command::{ApplicationCommandInteraction, ApplicationCommandOptionType};
+
+use crate::commands::follow;
+use crate::db;
+use crate::handler::BotData;
+use crate::helpers::{command_opt, command_resp};
+use crate::runtime::get_logger;
+
+pub const UNFOLLOW: &str = "unfollow";
+
+pub fn unfollow_builder(cmd: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    cmd.name(UNFOLLOW)
+        .description("Stop getting DMs about an artist's new releases")
+        .create_option(|opt| opt
+            .name("artist")
+            .description("The followed artist to stop getting DMs about")
+            .required(true)
+            .set_autocomplete(true)
+            .kind(ApplicationCommandOptionType::String))
+}
+
+pub async fn unfollow(ctx: Context, interaction: ApplicationCommandInteraction) -> anyhow::Result<()> {
+    let artist = command_opt::find_required(&ctx, &interaction, command_opt::find_string_opt, "artist").await?.unwrap();
+
+    command_resp::reply_deferred_ack(&ctx, &interaction).await?;
+
+    let user_id = interaction.user.id;
+
+    //
+
+    let data = ctx.data.read().await;
+    let data = data.get::<BotData>().unwrap();
+
+    let followed = db::model::list_user_followed_artists(data.db_client.conn(), *user_id.as_u64()).await?;
+
+    let subscription = match follow::find_followed_artist(&followed, &artist) {
+        None => {
+            command_resp::reply_deferred_result(&ctx, &interaction, "You don't follow that artist; pick one from the list.").await.unwrap();
+            return Ok(());
+        }
+        Some(v) => v,
+    };
+
+    db::model::remove_subscription(data.db_client.conn(), subscription.id_subscription).await?;
+
+    get_logger().info("Removed user subscription.", meta! {
+        "InteractionID" => interaction.id,
+        "UserID" => user_id,
+        "SubscriptionID" => subscription.id_subscription,
+        "ArtistID" => subscription.id_artist,
+    });
+
+    command_resp::reply_deferred_result(&ctx, &interaction, format!(
+        "You'll no longer get DMs about **{}**.", subscription.artist_name
+    )).await.unwrap();
+
+    Ok(())
+}
@@ -0,0 +1,82 @@
+use evlog::meta;
+use serenity::builder::CreateApplicationCommand;
+use serenity::client::Context;
+use serenity::model::interactions::application_command::{ApplicationCommandInteraction, ApplicationCommandOptionType};
+
+use crate::commands::watch;
+use crate::db;
+use crate::handler::BotData;
+use crate::helpers::{command_opt, command_resp, permissions};
+use crate::runtime::get_logger;
+
+pub const UNMONITOR: &str = "unmonitor";
+
+pub fn unmonitor_builder(cmd: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    cmd.name(UNMONITOR)
+        .description("Stop monitoring a Spotify artist")
+        .create_option(|opt| opt
+            .name("artist")
+            .description("The watched artist to stop monitoring")
+            .required(true)
+            .set_autocomplete(true)
+            .kind(ApplicationCommandOptionType::String))
+}
+
+pub async fn unmonitor(ctx: Context, interaction: ApplicationCommandInteraction) -> anyhow::Result<()> {
+    let artist = command_opt::find_required(&ctx, &interaction, command_opt::find_string_opt, "artist").await?.unwrap();
+
+    command_resp::reply_deferred_ack(&ctx, &interaction).await?;
+
+    //
+
+    let guild_id = match permissions::authorize_watch_manager(&ctx, &interaction, "/unmonitor").await? {
+        None => return Ok(()),
+        Some(v) => v,
+    };
+
+    //
+
+    let data = ctx.data.read().await;
+    let data = data.get::<BotData>().unwrap();
+
+    let watched = db::model::list_server_watched_artists(data.db_client.conn(), *guild_id.as_u64()).await?;
+
+    let watch = match watch::find_watched_artist(&watched, &artist) {
+        None => {
+            get_logger().info("Unknown artist passed to /unmonitor.", meta! {
+                "InteractionID" => interaction.id,
+                "GuildID" => guild_id,
+                "Artist" => artist,
+            });
+            command_resp::reply_deferred_result(&ctx, &interaction, "This server is not monitoring that artist; pick one from the list.").await.unwrap();
+            return Ok(());
+        }
+        Some(v) => v,
+    };
+
+    if let Err(e) = db::model::remove_watch(data.db_client.conn(), watch.id_watch).await {
+        get_logger().error("Failed to remove artist watch.", meta! {
+            "InteractionID" => interaction.id,
+            "GuildID" => guild_id,
+            "WatchID" => watch.id_watch,
+            "ArtistID" => watch.id_artist,
+            "Error" => e,
+        });
+
+        command_resp::reply_deferred_result(&ctx, &interaction, "Failed to remove artist watch.").await.unwrap();
+        return Ok(());
+    }
+
+    get_logger().info("Removed artist watch.", meta! {
+        "InteractionID" => interaction.id,
+        "GuildID" => guild_id,
+        "WatchID" => watch.id_watch,
+        "ArtistID" => watch.id_artist,
+    });
+
+    command_resp::reply_deferred_result(&ctx, &interaction, format!(
+        "Stopped monitoring **{}**.", watch.artist_name
+    )).await.unwrap();
+
+    Ok(())
+}
@@ -0,0 +1,396 @@
+use evlog::meta;
+use serenity::builder::CreateApplicationCommand;
+use serenity::client::Context;
+use serenity::model::id::ChannelId;
+use serenity::model::interactions::application_command::{ApplicationCommandInteraction, ApplicationCommandOptionType};
+use serenity::model::interactions::autocomplete::AutocompleteInteraction;
+
+use crate::db;
+use crate::db::schema::WatchedArtist;
+use crate::handler::BotData;
+use crate::monitor::{PAUSE_MODE_QUEUE, PAUSE_MODE_SKIP};
+use crate::helpers::{command_opt, command_resp, permissions};
+use crate::runtime::get_logger;
+use crate::spotify;
+use crate::spotify::release_types;
+
+pub const WATCH: &str = "watch";
+
+pub fn watch_builder(cmd: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    cmd.name(WATCH)
+        .description("Manage an existing artist watch")
+        .create_option(|sub| sub
+            .name("edit")
+            .description("Change the settings of an existing watch")
+            .kind(ApplicationCommandOptionType::SubCommand)
+            .create_sub_option(|opt| opt
+                .name("artist")
+                .description("The watched artist to edit")
+                .required(true)
+                .set_autocomplete(true)
+                .kind(ApplicationCommandOptionType::String))
+            .create_sub_option(|opt| opt
+                .name("channel")
+                .description("The channel to send alerts in")
+                .required(false)
+                .channel_types(permissions::ALERT_CHANNEL_TYPES)
+                .kind(ApplicationCommandOptionType::Channel))
+            .create_sub_option(|opt| opt
+                .name("markets")
+                .description("Country codes or presets to monitor releases in (ex: US; US, GB; EU; LATAM; NORDICS)")
+                .required(false)
+                .kind(ApplicationCommandOptionType::String))
+            .create_sub_option(|opt| opt
+                .name("release-types")
+                .description("Release types to alert on: album, single, compilation, appears_on")
+                .required(false)
+                .kind(ApplicationCommandOptionType::String))
+            .create_sub_option(|opt| opt
+                .name("mention-role")
+                .description("A role to mention in this artist's alerts")
+                .required(false)
+                .kind(ApplicationCommandOptionType::Role))
+            .create_sub_option(|opt| opt
+                .name("remove-mention-role")
+                .description("Stop mentioning a role in this artist's alerts")
+                .required(false)
+                .kind(ApplicationCommandOptionType::Boolean)))
+        .create_option(|sub| sub
+            .name("pause")
+            .description("Stop sending alerts for a watch, or for every watch in this server")
+            .kind(ApplicationCommandOptionType::SubCommand)
+            .create_sub_option(|opt| opt
+                .name("artist")
+                .description("The watched artist to pause (default: every watch in this server)")
+                .required(false)
+                .set_autocomplete(true)
+                .kind(ApplicationCommandOptionType::String))
+            .create_sub_option(|opt| opt
+                .name("mode")
+                .description("What to do with releases found while paused (default: queue)")
+                .required(false)
+                .add_string_choice("Queue them and send them on resume", PAUSE_MODE_QUEUE)
+                .add_string_choice("Skip them", PAUSE_MODE_SKIP)
+                .kind(ApplicationCommandOptionType::String)))
+        .create_option(|sub| sub
+            .name("resume")
+            .description("Resume sending alerts for a watch, or for this server")
+            .kind(ApplicationCommandOptionType::SubCommand)
+            .create_sub_option(|opt| opt
+                .name("artist")
+                .description("The watched artist to resume (default: lift the server-wide pause)")
+                .required(false)
+                .set_autocomplete(true)
+                .kind(ApplicationCommandOptionType::String)))
+}
+
+pub async fn watch(ctx: Context, interaction: ApplicationCommandInteraction) -> anyhow::Result<()> {
+    match command_opt::find_subcommand(&interaction) {
+        Some("edit") => edit(ctx, interaction).await,
+        Some("pause") => pause(ctx, interaction).await,
+        Some("resume") => resume(ctx, interaction).await,
+        _ => Ok(()),
+    }
+}
+
+async fn edit(ctx: Context, interaction: ApplicationCommandInteraction) -> anyhow::Result<()> {
+    let artist = command_opt::find_required(&ctx, &interaction, command_opt::find_string_opt, "artist").await?.unwrap();
+    let channel_id = command_opt::find_channel_opt(command_opt::command_options(&interaction), "channel").map(|v| v.id);
+    let markets_input = command_opt::find_string_opt(command_opt::command_options(&interaction), "markets");
+    let types_input = command_opt::find_string_opt(command_opt::command_options(&interaction), "release-types");
+    let mention_role = command_opt::find_role_opt(command_opt::command_options(&interaction), "mention-role");
+    let remove_mention_role = command_opt::find_boolean_opt(command_opt::command_options(&interaction), "remove-mention-role").unwrap_or(false);
+
+    command_resp::reply_deferred_ack(&ctx, &interaction).await?;
+
+    let guild_id = match permissions::authorize_watch_manager(&ctx, &interaction, "/watch").await? {
+        None => return Ok(()),
+        Some(v) => v,
+    };
+
+    if mention_role.is_some() && remove_mention_role {
+        command_resp::reply_deferred_result(&ctx, &interaction, "Pass either `mention-role` or `remove-mention-role`, not both.").await.unwrap();
+        return Ok(());
+    }
+
+    //
+
+    let data = ctx.data.read().await;
+    let data = data.get::<BotData>().unwrap();
+
+    let watched = db::model::list_server_watched_artists(data.db_client.conn(), *guild_id.as_u64()).await?;
+
+    let watch = match find_watched_artist(&watched, &artist) {
+        None => {
+            command_resp::reply_deferred_result(&ctx, &interaction, "This server is not monitoring that artist; pick one from the list.").await.unwrap();
+            return Ok(());
+        }
+        Some(v) => v,
+    };
+
+    let current = match db::model::get_watch(data.db_client.conn(), watch.id_watch).await? {
+        None => {
+            command_resp::reply_deferred_result(&ctx, &interaction, "This server is not monitoring that artist; pick one from the list.").await.unwrap();
+            return Ok(());
+        }
+        Some(v) => v,
+    };
+
+    let mut id_alert_channel = current.id_alert_channel;
+    let mut markets = current.markets;
+    let mut album_groups = current.album_groups;
+    let mut id_mention_role = current.id_mention_role;
+    let mut changes = Vec::new();
+
+    if let Some(channel_id) = channel_id {
+        if let Err(e) = permissions::check_alert_channel(&ctx.cache, guild_id, channel_id) {
+            command_resp::reply_deferred_result(&ctx, &interaction, e.description(channel_id)).await.unwrap();
+            return Ok(());
+        }
+
+        id_alert_channel = *channel_id.as_u64();
+        changes.push(format!("alerts now go to <#{}>", channel_id));
+    }
+
+    if let Some(markets_input) = markets_input {
+        markets = match spotify::markets::parse_markets(&markets_input) {
+            Ok(v) => v,
+            Err(e) => {
+                command_resp::reply_deferred_result(&ctx, &interaction, e).await.unwrap();
+                return Ok(());
+            }
+        };
+
+        changes.push(format!("markets are now {}", spotify::markets::describe_markets(&markets)));
+    }
+
+    if let Some(types_input) = types_input {
+        album_groups = match release_types::parse_release_types(&types_input) {
+            Ok(v) => v,
+            Err(e) => {
+                command_resp::reply_deferred_result(&ctx, &interaction, e).await.unwrap();
+                return Ok(());
+            }
+        };
+
+        changes.push(format!("release types are now {}", release_types::describe_release_types(&album_groups)));
+    }
+
+    if let Some(role) = mention_role {
+        id_mention_role = Some(*role.id.as_u64());
+        changes.push(format!("alerts now mention <@&{}>", role.id));
+    } else if remove_mention_role {
+        id_mention_role = None;
+        changes.push("alerts no longer mention a role".to_owned());
+    }
+
+    // A disabled watch is re-enabled by any edit that leaves it with a usable channel, including
+    // one that changes nothing once the channel has been fixed.
+    if let Some(reason) = &current.disabled_reason {
+        if channel_id.is_none() {
+            if let Err(e) = permissions::check_alert_channel(&ctx.cache, guild_id, ChannelId(id_alert_channel)) {
+                command_resp::reply_deferred_result(&ctx, &interaction, format!(
+                    "This watch was disabled because {}, and it still can't be turned back on: {} Fix the channel, or pick a new one with `channel`.",
+                    reason, e.description(ChannelId(id_alert_channel)),
+                )).await.unwrap();
+                return Ok(());
+            }
+        }
+
+        changes.push("alerts are turned back on".to_owned());
+    }
+
+    if changes.is_empty() {
+        command_resp::reply_deferred_result(&ctx, &interaction, "Nothing to change; pass at least one setting to edit.").await.unwrap();
+        return Ok(());
+    }
+
+    db::model::update_watch_settings(data.db_client.conn(), watch.id_watch, id_alert_channel, markets, album_groups, id_mention_role).await?;
+
+    get_logger().info("Edited artist watch.", meta! {
+        "InteractionID" => interaction.id,
+        "GuildID" => guild_id,
+        "WatchID" => watch.id_watch,
+        "ArtistID" => watch.id_artist,
+    });
+
+    command_resp::reply_deferred_result(&ctx, &interaction, format!(
+        "Updated the watch for **{}**: {}.", watch.artist_name, changes.join("; ")
+    )).await.unwrap();
+
+    Ok(())
+}
+
+async fn pause(ctx: Context, interaction: ApplicationCommandInteraction) -> anyhow::Result<()> {
+    let artist = command_opt::find_string_opt(command_opt::command_options(&interaction), "artist");
+    let mode = command_opt::find_string_opt(command_opt::command_options(&interaction), "mode")
+        .unwrap_or_else(|| PAUSE_MODE_QUEUE.to_owned());
+
+    command_resp::reply_deferred_ack(&ctx, &interaction).await?;
+
+    let guild_id = match permissions::authorize_watch_manager(&ctx, &interaction, "/watch").await? {
+        None => return Ok(()),
+        Some(v) => v,
+    };
+
+    let data = ctx.data.read().await;
+    let data = data.get::<BotData>().unwrap();
+
+    let handling = if mode == PAUSE_MODE_SKIP {
+        "releases found while paused will be skipped"
+    } else {
+        "releases found while paused will be sent on resume"
+    };
+
+    let artist = match artist {
+        None => {
+            db::model::set_guild_pause_mode(data.db_client.conn(), *guild_id.as_u64(), Some(&mode)).await?;
+
+            get_logger().info("Paused all artist watches.", meta! {
+                "InteractionID" => interaction.id,
+                "GuildID" => guild_id,
+                "Mode" => mode,
+            });
+
+            command_resp::reply_deferred_result(&ctx, &interaction, format!(
+                "Paused every watch in this server; {}. Use /watch resume to resume.", handling
+            )).await.unwrap();
+            return Ok(());
+        }
+        Some(v) => v,
+    };
+
+    let watched = db::model::list_server_watched_artists(data.db_client.conn(), *guild_id.as_u64()).await?;
+
+    let watch = match find_watched_artist(&watched, &artist) {
+        None => {
+            command_resp::reply_deferred_result(&ctx, &interaction, "This server is not monitoring that artist; pick one from the list.").await.unwrap();
+            return Ok(());
+        }
+        Some(v) => v,
+    };
+
+    db::model::set_watch_pause_mode(data.db_client.conn(), watch.id_watch, Some(&mode)).await?;
+
+    get_logger().info("Paused artist watch.", meta! {
+        "InteractionID" => interaction.id,
+        "GuildID" => guild_id,
+        "WatchID" => watch.id_watch,
+        "Mode" => mode,
+    });
+
+    command_resp::reply_deferred_result(&ctx, &interaction, format!(
+        "Paused the watch for **{}**; {}.", watch.artist_name, handling
+    )).await.unwrap();
+
+    Ok(())
+}
+
+async fn resume(ctx: Context, interaction: ApplicationCommandInteraction) -> anyhow::Result<()> {
+    let artist = command_opt::find_string_opt(command_opt::command_options(&interaction), "artist");
+
+    command_resp::reply_deferred_ack(&ctx, &interaction).await?;
+
+    let guild_id = match permissions::authorize_watch_manager(&ctx, &interaction, "/watch").await? {
+        None => return Ok(()),
+        Some(v) => v,
+    };
+
+    let data = ctx.data.read().await;
+    let data = data.get::<BotData>().unwrap();
+
+    let artist = match artist {
+        None => {
+            let settings = db::model::get_guild_settings(data.db_client.conn(), *guild_id.as_u64()).await?;
+            if settings.pause_mode.is_none() {
+                command_resp::reply_deferred_result(&ctx, &interaction, "This server is not paused; pass an artist to resume a single watch.").await.unwrap();
+                return Ok(());
+            }
+
+            db::model::set_guild_pause_mode(data.db_client.conn(), *guild_id.as_u64(), None).await?;
+
+            let still_paused = db::model::list_watches(data.db_client.conn(), *guild_id.as_u64()).await?
+                .iter()
+                .filter(|v| v.pause_mode.is_some())
+                .count();
+
+            get_logger().info("Resumed all artist watches.", meta! {
+                "InteractionID" => interaction.id,
+                "GuildID" => guild_id,
+            });
+
+            command_resp::reply_deferred_result(&ctx, &interaction, if still_paused == 0 {
+                "Resumed alerts for this server.".to_owned()
+            } else {
+                format!("Resumed alerts for this server; {} individually paused watches are still paused.", still_paused)
+            }).await.unwrap();
+            return Ok(());
+        }
+        Some(v) => v,
+    };
+
+    let watched = db::model::list_server_watched_artists(data.db_client.conn(), *guild_id.as_u64()).await?;
+
+    let watch = match find_watched_artist(&watched, &artist) {
+        None => {
+            command_resp::reply_deferred_result(&ctx, &interaction, "This server is not monitoring that artist; pick one from the list.").await.unwrap();
+            return Ok(());
+        }
+        Some(v) => v,
+    };
+
+    db::model::set_watch_pause_mode(data.db_client.conn(), watch.id_watch, None).await?;
+
+    get_logger().info("Resumed artist watch.", meta! {
+        "InteractionID" => interaction.id,
+        "GuildID" => guild_id,
+        "WatchID" => watch.id_watch,
+    });
+
+    let settings = db::model::get_guild_settings(data.db_client.conn(), *guild_id.as_u64()).await?;
+
+    command_resp::reply_deferred_result(&ctx, &interaction, if settings.pause_mode.is_none() {
+        format!("Resumed the watch for **{}**.", watch.artist_name)
+    } else {
+        format!("Resumed the watch for **{}**, but this whole server is still paused; use /watch resume without an artist to lift that.", watch.artist_name)
+    }).await.unwrap();
+
+    Ok(())
+}
+
+/// Finds the watch an `artist` option refers to. The option value is the artist ID when picked
+/// from autocomplete, but users may also type a name.
+#[must_use]
+pub fn find_watched_artist<'a>(watched: &'a [WatchedArtist], input: &str) -> Option<&'a WatchedArtist> {
+    watched.iter().find(|v| v.id_artist == input)
+        .or_else(|| watched.iter().find(|v| v.artist_name.eq_ignore_ascii_case(input)))
+}
+
+/// Autocompletes an `artist` option with the artists the server is watching.
+pub async fn watched_artist_autocomplete(ctx: Context, interaction: AutocompleteInteraction) -> anyhow::Result<()> {
+    let guild_id = match interaction.guild_id {
+        None => return Ok(()),
+        Some(v) => v,
+    };
+
+    let input = match command_opt::find_focused_opt(&interaction.data.options) {
+        None => return Ok(()),
+        Some(v) => v.value.to_lowercase(),
+    };
+
+    let data = ctx.data.read().await;
+    let data = data.get::<BotData>().unwrap();
+
+    let watched = db::model::list_server_watched_artists(data.db_client.conn(), *guild_id.as_u64()).await?;
+
+    interaction.create_autocomplete_response(&ctx.http, |r| {
+        watched.iter()
+            .filter(|v| v.artist_name.to_lowercase().contains(&input))
+            .take(25)
+            .for_each(|v| { r.add_string_choice(&v.artist_name, &v.id_artist); });
+
+        r
+    }).await?;
+
+    Ok(())
+}
@@ -0,0 +1,226 @@
+use std::collections::HashMap;
+
+use serenity::builder::{CreateApplicationCommand, CreateComponents, CreateEmbed};
+use serenity::client::Context;
+use serenity::model::interactions::application_command::{ApplicationCommandInteraction, ApplicationCommandOptionType};
+use serenity::model::interactions::message_component::{ButtonStyle, MessageComponentInteraction};
+use serenity::utils::{Color, MessageBuilder};
+
+use crate::commands::{export, import};
+use crate::db;
+use crate::db::schema::{Watch, WatchedArtist};
+use crate::handler::BotData;
+use crate::helpers::{command_opt, command_resp, permissions};
+use crate::monitor::PAUSE_MODE_SKIP;
+use crate::spotify::{markets, release_types};
+
+pub const WATCHES: &str = "watches";
+pub const WATCHES_PAGE: &str = "watches-page";
+
+const PAGE_SIZE: usize = 10;
+
+pub fn watches_builder(cmd: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    cmd.name(WATCHES)
+        .description("List and manage the Spotify artists this server is monitoring")
+        .create_option(|sub| sub
+            .name("list")
+            .description("List the Spotify artists this server is monitoring")
+            .kind(ApplicationCommandOptionType::SubCommand))
+        .create_option(|sub| sub
+            .name("export")
+            .description("Download this server's watches and alert history as JSON and CSV files")
+            .kind(ApplicationCommandOptionType::SubCommand))
+        .create_option(|sub| sub
+            .name("import")
+            .description("Monitor every artist in a CSV or JSON file")
+            .kind(ApplicationCommandOptionType::SubCommand)
+            .create_sub_option(|opt| opt
+                .name("file")
+                .description("A CSV or JSON file of artist links, IDs or names, with optional markets and channel columns")
+                .required(true)
+                .kind(ApplicationCommandOptionType::Attachment))
+            .create_sub_option(|opt| opt
+                .name("markets")
+                .description("Markets for rows that don't list their own (ex: US; US, GB; EU; LATAM; NORDICS)")
+                .required(false)
+                .kind(ApplicationCommandOptionType::String))
+            .create_sub_option(|opt| opt
+                .name("channel")
+                .description("The channel for rows that don't list their own (default: the server's default, or this channel)")
+                .required(false)
+                .channel_types(permissions::ALERT_CHANNEL_TYPES)
+                .kind(ApplicationCommandOptionType::Channel))
+            .create_sub_option(|opt| opt
+                .name("release-types")
+                .description("Release types to alert on: album, single, compilation, appears_on (default: server default)")
+                .required(false)
+                .kind(ApplicationCommandOptionType::String)))
+}
+
+pub async fn watches(ctx: Context, interaction: ApplicationCommandInteraction) -> anyhow::Result<()> {
+    match command_opt::find_subcommand(&interaction) {
+        Some("list") => list(ctx, interaction).await,
+        Some("export") => export::export(ctx, interaction).await,
+        Some("import") => import::import(ctx, interaction).await,
+        _ => Ok(()),
+    }
+}
+
+async fn list(ctx: Context, interaction: ApplicationCommandInteraction) -> anyhow::Result<()> {
+    let guild_id = match interaction.guild_id {
+        None => {
+            command_resp::reply(&ctx, &interaction, |r| r.content("/watches list can only be used in a server.")).await?;
+            return Ok(());
+        }
+        Some(v) => v,
+    };
+
+    let page = load_page(&ctx, *guild_id.as_u64(), 0).await?;
+
+    command_resp::reply(&ctx, &interaction, |r| match page {
+        None => r.content("This server is not monitoring any artists; use /monitor to add one."),
+        Some(page) => r
+            .add_embed(page.embed)
+            .set_components(page.components),
+    }).await?;
+
+    Ok(())
+}
+
+/// Handles the Previous/Next buttons; the custom ID is `watches-page:<page>`.
+pub async fn watches_page(ctx: Context, interaction: MessageComponentInteraction) -> anyhow::Result<()> {
+    let guild_id = match interaction.guild_id {
+        None => return Ok(()),
+        Some(v) => v,
+    };
+
+    let page_num = interaction.data.custom_id
+        .split(':')
+        .nth(1)
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or(0);
+
+    let page = load_page(&ctx, *guild_id.as_u64(), page_num).await?;
+
+    command_resp::update_message(&ctx, &interaction, |r| match page {
+        None => r
+            .content("This server is not monitoring any artists; use /monitor to add one.")
+            .set_embeds(Vec::new())
+            .set_components(CreateComponents::default()),
+        Some(page) => r
+            .set_embeds(vec![page.embed])
+            .set_components(page.components),
+    }).await?;
+
+    Ok(())
+}
+
+struct WatchesPage {
+    embed: CreateEmbed,
+    components: CreateComponents,
+}
+
+async fn load_page(ctx: &Context, id_server: u64, page_num: usize) -> anyhow::Result<Option<WatchesPage>> {
+    let data = ctx.data.read().await;
+    let data = data.get::<BotData>().unwrap();
+
+    let watches = db::model::list_watches(data.db_client.conn(), id_server).await?;
+    if watches.is_empty() {
+        return Ok(None);
+    }
+
+    let artists = db::model::list_server_watched_artists(data.db_client.conn(), id_server).await?
+        .into_iter()
+        .map(|v| (v.id_artist.clone(), v))
+        .collect::<HashMap<_, _>>();
+
+    let settings = db::model::get_guild_settings(data.db_client.conn(), id_server).await?;
+
+    let page_count = (watches.len() + PAGE_SIZE - 1) / PAGE_SIZE;
+    let page_num = page_num.min(page_count - 1);
+
+    let page = &watches[page_num * PAGE_SIZE..((page_num + 1) * PAGE_SIZE).min(watches.len())];
+
+    Ok(Some(WatchesPage {
+        embed: build_embed(page, &artists, settings.pause_mode.as_deref(), page_num, page_count, watches.len()),
+        components: build_components(page_num, page_count),
+    }))
+}
+
+fn build_embed(watches: &[Watch], artists: &HashMap<String, WatchedArtist>, guild_pause_mode: Option<&str>, page_num: usize, page_count: usize, total: usize) -> CreateEmbed {
+    let mut e = CreateEmbed::default();
+
+    e.author(|a| {
+        a.name("Spotlit");
+        a.icon_url("https://i.imgur.com/iKUvWHR.png");
+
+        a
+    });
+
+    e.title("Monitored artists");
+    e.color(Color::from_rgb(30, 215, 96));
+    if let Some(mode) = guild_pause_mode {
+        e.description(format!("Alerts are paused for this whole server ({}).", describe_pause_mode(mode)));
+    }
+
+    let first_image = watches.iter()
+        .filter_map(|v| artists.get(&v.id_artist))
+        .find_map(|v| v.artist_image_url.as_ref());
+    if let Some(image_url) = first_image {
+        e.thumbnail(image_url);
+    }
+
+    for watch in watches {
+        let name = artists.get(&watch.id_artist).map_or(&watch.id_artist, |v| &v.artist_name);
+
+        e.field(name, MessageBuilder::new()
+            .push("Markets: ").push_mono(markets::describe_markets(&watch.markets))
+            .push(" · Channel: ").channel(watch.id_alert_channel)
+            .push("\nRelease types: ").push(release_types::describe_release_types(&watch.album_groups))
+            .push("\nCreated by ").user(watch.id_created_by)
+            .push(format!(" <t:{}:R>", watch.time_created.timestamp()))
+            .push("\nLast scanned: ").push(if watch.time_last_scanned.timestamp() == 0 {
+                "never".to_owned()
+            } else {
+                format!("<t:{}:R>", watch.time_last_scanned.timestamp())
+            })
+            .push(watch.id_mention_role.map_or(String::new(), |v| format!("\nMentions <@&{}>", v)))
+            .push(watch.pause_mode.as_deref().map_or(String::new(), |v| format!("\nPaused ({})", describe_pause_mode(v))))
+            .push(watch.disabled_reason.as_deref().map_or(String::new(), |v| format!("\nDisabled because {}; fix it with /watch edit", v)))
+            .build(), false);
+    }
+
+    e.footer(|f| f.text(format!("Page {} of {} · {} artists", page_num + 1, page_count, total)));
+
+    e
+}
+
+fn describe_pause_mode(mode: &str) -> &str {
+    if mode == PAUSE_MODE_SKIP {
+        "skipping new releases"
+    } else {
+        "queueing new releases"
+    }
+}
+
+fn build_components(page_num: usize, page_count: usize) -> CreateComponents {
+    let mut c = CreateComponents::default();
+
+    if page_count <= 1 {
+        return c;
+    }
+
+    c.create_action_row(|r| r
+        .create_button(|b| b
+            .custom_id(format!("{}:{}", WATCHES_PAGE, page_num.saturating_sub(1)))
+            .label("Previous")
+            .style(ButtonStyle::Secondary)
+            .disabled(page_num == 0))
+        .create_button(|b| b
+            .custom_id(format!("{}:{}", WATCHES_PAGE, page_num + 1))
+            .label("Next")
+            .style(ButtonStyle::Secondary)
+            .disabled(page_num + 1 >= page_count)));
+
+    c
+}
@@ -5,35 +5,90 @@ use tokio_stream::StreamExt;
 use crate::db::PGExec;
 use crate::db::schema::*;
 
-pub async fn add_watch(
+pub async fn upsert_artist(
     conn: &PgPool,
+    id_artist: &str,
+    name: &str,
+    image_url: Option<&str>,
+    genres: Vec<String>,
+    followers: i32,
+    popularity: i32,
+) -> anyhow::Result<Artist> {
+    let r = query!(
+        "INSERT INTO artist (id_artist, time_updated, name, image_url, genres, followers, popularity)
+         VALUES ($1, NOW(), $2, $3, $4, $5, $6)
+         ON CONFLICT ON CONSTRAINT artist_pk DO
+         UPDATE SET time_updated=NOW(), name=$2, image_url=$3, genres=$4, followers=$5, popularity=$6
+         RETURNING time_updated",
+        id_artist, name, image_url, &genres, followers, popularity)
+        .fetch_one(conn)
+        .await?;
+
+    Ok(Artist {
+        id_artist: id_artist.to_owned(),
+        time_updated: r.time_updated,
+        name: name.to_owned(),
+        image_url: image_url.map(|v| v.to_owned()),
+        genres,
+        followers,
+        popularity,
+    })
+}
+
+pub async fn list_stale_artists<'a, TDB: PGExec<'a>>(conn: TDB, updated_before: DateTime<Utc>) -> anyhow::Result<Vec<String>> {
+    let mut stream = query!(
+        "SELECT a.id_artist
+         FROM artist a
+         INNER JOIN vw_all_watched_artists wa ON a.id_artist = wa.id_artist
+         WHERE a.time_updated < $1",
+        updated_before)
+        .map(|r| r.id_artist)
+        .fetch(conn);
+
+    let mut result = Vec::new();
+    while let Some(row) = stream.try_next().await? {
+        result.push(row);
+    }
+
+    Ok(result)
+}
+
+/// Creates a watch, or returns `None` if the server already watches the artist. Existing watches
+/// are left untouched; they're changed through `update_watch_settings`.
+pub async fn add_watch<'a, TDB: PGExec<'a>>(
+    conn: TDB,
     id_created_by: u64,
     id_server: u64,
     id_alert_channel: u64,
     id_artist: &str,
-    market: &str,
-) -> anyhow::Result<Watch> {
+    markets: Vec<String>,
+    album_groups: Vec<String>,
+    id_mention_role: Option<u64>,
+) -> anyhow::Result<Option<Watch>> {
     let r = query!(
-        "INSERT INTO watch (time_created, id_created_by, id_server, id_alert_channel, id_artist, market, has_initialized, time_last_scanned)
-         VALUES (NOW(), $1, $2, $3, $4, $5, FALSE, TO_TIMESTAMP(0))
-         ON CONFLICT ON CONSTRAINT watch_server_artist_uniq DO
-         UPDATE SET time_created=NOW(), id_created_by=$1, id_server=$2, id_alert_channel=$3, id_artist=$4, market=$5, has_initialized=FALSE, time_last_scanned=TO_TIMESTAMP(0)
+        "INSERT INTO watch (time_created, id_created_by, id_server, id_alert_channel, id_artist, markets, album_groups, id_mention_role, has_initialized, time_last_scanned)
+         VALUES (NOW(), $1, $2, $3, $4, $5, $6, $7, FALSE, TO_TIMESTAMP(0))
+         ON CONFLICT ON CONSTRAINT watch_server_artist_uniq DO NOTHING
          RETURNING id, time_created, time_last_scanned;",
-        id_created_by.to_string(), id_server.to_string(), id_alert_channel.to_string(), id_artist, market)
-        .fetch_one(conn)
+        id_created_by.to_string(), id_server.to_string(), id_alert_channel.to_string(), id_artist, &markets, &album_groups, id_mention_role.map(|v| v.to_string()))
+        .fetch_optional(conn)
         .await?;
 
-    Ok(Watch {
+    Ok(r.map(|r| Watch {
         id: r.id,
         time_created: r.time_created,
         id_created_by,
         id_server,
         id_alert_channel,
         id_artist: id_artist.to_owned(),
-        market: market.to_owned(),
+        markets,
+        album_groups,
         has_initialized: false,
         time_last_scanned: r.time_last_scanned,
-    })
+        pause_mode: None,
+        id_mention_role,
+        disabled_reason: None,
+    }))
 }
 
 pub async fn update_watch(
@@ -51,6 +106,83 @@ pub async fn update_watch(
     Ok(())
 }
 
+/// Changes a watch's alert channel, markets, release types and mention role in place, and
+/// re-enables it if it was disabled. Its creator, scan state and alert history are kept.
+///
+/// Releases that only become visible through newly added markets are recorded as alerted, so
+/// widening the markets doesn't re-alert the artist's back catalogue.
+pub async fn update_watch_settings(
+    conn: &PgPool,
+    id: i32,
+    id_alert_channel: u64,
+    markets: Vec<String>,
+    album_groups: Vec<String>,
+    id_mention_role: Option<u64>,
+) -> anyhow::Result<()> {
+    let mut tx = conn.begin().await?;
+
+    query!(
+        "INSERT INTO artist_release_watch_alerted (id_release, id_watch, outcome)
+         SELECT ar.id_release, w.id, 'skipped'
+         FROM watch w
+         INNER JOIN artist_release ar ON w.id_artist = ar.id_artist
+         WHERE w.id = $1
+           AND ar.available_markets && $2
+           AND NOT (ar.available_markets && w.markets)
+         ON CONFLICT DO NOTHING",
+        id, &markets)
+        .execute(&mut tx)
+        .await?;
+
+    query!(
+        "UPDATE watch SET id_alert_channel=$1, markets=$2, album_groups=$3, id_mention_role=$4, disabled_reason=NULL WHERE id = $5",
+        id_alert_channel.to_string(), &markets, &album_groups, id_mention_role.map(|v| v.to_string()), id)
+        .execute(&mut tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+pub async fn set_watch_pause_mode(
+    conn: &PgPool,
+    id: i32,
+    pause_mode: Option<&str>,
+) -> anyhow::Result<()> {
+    query!(
+        "UPDATE watch SET pause_mode=$1 WHERE id = $2",
+        pause_mode, id)
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
+/// Disables every watch that sends alerts to a channel of a server, and returns the watches that
+/// weren't disabled already.
+pub async fn disable_channel_watches(conn: &PgPool, id_server: u64, id_alert_channel: u64, disabled_reason: &str) -> anyhow::Result<Vec<DisabledWatch>> {
+    let mut stream = query!(
+        "UPDATE watch w SET disabled_reason=$1
+         FROM artist a
+         WHERE a.id_artist = w.id_artist AND w.id_server = $2 AND w.id_alert_channel = $3 AND w.disabled_reason IS NULL
+         RETURNING w.id, w.id_created_by, a.name",
+        disabled_reason, id_server.to_string(), id_alert_channel.to_string())
+        .map(|r| DisabledWatch {
+            id_watch: r.id,
+            id_created_by: r.id_created_by.parse::<u64>().unwrap(),
+            artist_name: r.name,
+        })
+        .fetch(conn);
+
+    let mut result = Vec::new();
+    while let Some(row) = stream.try_next().await? {
+        result.push(row);
+    }
+
+    Ok(result)
+}
+
 macro_rules! map_watch {
     ($v: expr) => { Watch {
         id: $v.id,
@@ -59,17 +191,36 @@ macro_rules! map_watch {
         id_server: $v.id_server.parse::< u64 > ().unwrap(),
         id_alert_channel: $v.id_alert_channel.parse::< u64 > ().unwrap(),
         id_artist: $v.id_artist,
-        market: $v.market,
+        markets: $v.markets,
+        album_groups: $v.album_groups,
         has_initialized: $v.has_initialized,
         time_last_scanned: $v.time_last_scanned,
+        pause_mode: $v.pause_mode,
+        id_mention_role: $v.id_mention_role.map(|v| v.parse::< u64 > ().unwrap()),
+        disabled_reason: $v.disabled_reason,
     } }
 }
 
-pub async fn list_watches<'a, TDB: PGExec<'a>>(conn: TDB) -> anyhow::Result<Vec<Watch>> {
+pub async fn get_watch<'a, TDB: PGExec<'a>>(conn: TDB, id: i32) -> anyhow::Result<Option<Watch>> {
+    let r = query!(
+        "SELECT *
+         FROM watch
+         WHERE id = $1",
+        id)
+        .map(|r| map_watch!(r))
+        .fetch_optional(conn)
+        .await?;
+
+    Ok(r)
+}
+
+pub async fn list_watches<'a, TDB: PGExec<'a>>(conn: TDB, id_server: u64) -> anyhow::Result<Vec<Watch>> {
     let mut stream = query!(
         "SELECT *
-         FROM watch"
-        )
+         FROM watch
+         WHERE id_server = $1
+         ORDER BY time_created",
+        id_server.to_string())
         .map(|r| map_watch!(r))
         .fetch(conn);
 
@@ -81,6 +232,62 @@ pub async fn list_watches<'a, TDB: PGExec<'a>>(conn: TDB) -> anyhow::Result<Vec<
     Ok(result)
 }
 
+pub async fn list_server_watched_artists<'a, TDB: PGExec<'a>>(conn: TDB, id_server: u64) -> anyhow::Result<Vec<WatchedArtist>> {
+    let mut stream = query!(
+        "SELECT w.id, w.id_artist, a.name, a.image_url
+         FROM watch w
+         INNER JOIN artist a ON w.id_artist = a.id_artist
+         WHERE w.id_server = $1
+         ORDER BY a.name",
+        id_server.to_string())
+        .map(|r| WatchedArtist {
+            id_watch: r.id,
+            id_artist: r.id_artist,
+            artist_name: r.name,
+            artist_image_url: r.image_url,
+        })
+        .fetch(conn);
+
+    let mut result = Vec::new();
+    while let Some(row) = stream.try_next().await? {
+        result.push(row);
+    }
+
+    Ok(result)
+}
+
+pub async fn remove_watch(conn: &PgPool, id_watch: i32) -> anyhow::Result<()> {
+    let mut tx = conn.begin().await?;
+
+    query!("DELETE FROM artist_release_watch_alerted WHERE id_watch = $1", id_watch)
+        .execute(&mut tx)
+        .await?;
+
+    query!("DELETE FROM watch WHERE id = $1", id_watch)
+        .execute(&mut tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+macro_rules! map_pending_release {
+    ($v: expr) => { PendingRelease {
+        artist_name: $v.artist_name.unwrap(),
+        artist_image_url: $v.artist_image_url,
+        id_release: $v.id_release.unwrap(),
+        id_artist: $v.id_artist.unwrap(),
+        artist_names: $v.artist_names.unwrap(),
+        album_type: $v.album_type.unwrap(),
+        album_group: $v.album_group.unwrap(),
+        href: $v.href.unwrap(),
+        image_url: $v.image_url.unwrap(),
+        name: $v.name.unwrap(),
+        release_date: $v.release_date.unwrap(),
+    } }
+}
+
 pub async fn list_unalerted_watches<'a, TDB: PGExec<'a>>(conn: TDB) -> anyhow::Result<Vec<PendingWatchAlert>> {
     let mut stream = query!("SELECT * FROM vw_unalerted_watches")
         .map(|r| PendingWatchAlert {
@@ -88,14 +295,13 @@ pub async fn list_unalerted_watches<'a, TDB: PGExec<'a>>(conn: TDB) -> anyhow::R
             has_initialized: r.has_initialized.unwrap(),
             id_server: r.id_server.unwrap().parse::<u64>().unwrap(),
             id_alert_channel: r.id_alert_channel.unwrap().parse::<u64>().unwrap(),
-            market: r.market.unwrap(),
-            id_release: r.id_release.unwrap(),
-            artist_names: r.artist_names.unwrap(),
-            album_type: r.album_type.unwrap(),
-            href: r.href.unwrap(),
-            image_url: r.image_url.unwrap(),
-            name: r.name.unwrap(),
-            release_date: r.release_date.unwrap(),
+            markets: r.markets.unwrap(),
+            id_mention_role: r.id_mention_role.map(|v| v.parse::<u64>().unwrap()),
+            pause_mode: r.pause_mode,
+            embed_style: r.embed_style.unwrap(),
+            matches_filter: r.matches_filter.unwrap(),
+            attempts: r.attempts.unwrap(),
+            release: map_pending_release!(r),
         })
         .fetch(conn);
 
@@ -107,15 +313,88 @@ pub async fn list_unalerted_watches<'a, TDB: PGExec<'a>>(conn: TDB) -> anyhow::R
     Ok(result)
 }
 
-pub async fn set_watch_alerted(
+macro_rules! map_alerted_release {
+    ($v: expr) => { AlertedRelease {
+        id_watch: $v.id_watch,
+        id_release: $v.id_release,
+        artist_name: $v.artist_name,
+        name: $v.name.unwrap_or_default(),
+        album_group: $v.album_group,
+        release_date: $v.release_date,
+        href: $v.href,
+        outcome: $v.outcome,
+        id_channel: $v.id_channel.map(|v| v.parse::< u64 > ().unwrap()),
+        id_message: $v.id_message.map(|v| v.parse::< u64 > ().unwrap()),
+        time_sent: $v.time_sent,
+        attempts: $v.attempts,
+        last_error: $v.last_error,
+        time_next_attempt: $v.time_next_attempt,
+    } }
+}
+
+pub async fn list_server_alerted_releases<'a, TDB: PGExec<'a>>(conn: TDB, id_server: u64) -> anyhow::Result<Vec<AlertedRelease>> {
+    let mut stream = query!(
+        "SELECT arwa.id_watch, ar.id_release, a.name AS artist_name, ar.name, ar.album_group, ar.release_date, ar.href,
+                arwa.outcome, arwa.id_channel, arwa.id_message, arwa.time_sent, arwa.attempts, arwa.last_error, arwa.time_next_attempt
+         FROM artist_release_watch_alerted arwa
+         INNER JOIN watch w ON arwa.id_watch = w.id
+         INNER JOIN artist a ON w.id_artist = a.id_artist
+         INNER JOIN artist_release ar ON arwa.id_release = ar.id_release AND w.id_artist = ar.id_artist
+         WHERE w.id_server = $1
+         ORDER BY arwa.id_watch, ar.release_date DESC",
+        id_server.to_string())
+        .map(|r| map_alerted_release!(r))
+        .fetch(conn);
+
+    let mut result = Vec::new();
+    while let Some(row) = stream.try_next().await? {
+        result.push(row);
+    }
+
+    Ok(result)
+}
+
+/// Lists a server's most recent alert deliveries, sent, failed or dead-lettered, newest first.
+pub async fn list_server_alert_history<'a, TDB: PGExec<'a>>(conn: TDB, id_server: u64, limit: i64) -> anyhow::Result<Vec<AlertedRelease>> {
+    let mut stream = query!(
+        "SELECT arwa.id_watch, ar.id_release, a.name AS artist_name, ar.name, ar.album_group, ar.release_date, ar.href,
+                arwa.outcome, arwa.id_channel, arwa.id_message, arwa.time_sent, arwa.attempts, arwa.last_error, arwa.time_next_attempt
+         FROM artist_release_watch_alerted arwa
+         INNER JOIN watch w ON arwa.id_watch = w.id
+         INNER JOIN artist a ON w.id_artist = a.id_artist
+         INNER JOIN artist_release ar ON arwa.id_release = ar.id_release AND w.id_artist = ar.id_artist
+         WHERE w.id_server = $1
+           AND arwa.outcome IN ('sent', 'failed', 'dead')
+         ORDER BY arwa.time_sent DESC
+         LIMIT $2",
+        id_server.to_string(), limit)
+        .map(|r| map_alerted_release!(r))
+        .fetch(conn);
+
+    let mut result = Vec::new();
+    while let Some(row) = stream.try_next().await? {
+        result.push(row);
+    }
+
+    Ok(result)
+}
+
+/// Records the outcome of delivering a release to a watch. Failed deliveries are recorded with
+/// `record_watch_alert_failure` instead, and overwritten once a later attempt succeeds.
+pub async fn record_watch_alert(
     conn: &PgPool,
     id_release: &str,
     id_watch: i32,
+    outcome: &str,
+    id_channel: Option<u64>,
+    id_message: Option<u64>,
 ) -> anyhow::Result<()> {
     query!(
-        "INSERT INTO artist_release_watch_alerted (id_release, id_watch)
-         VALUES ($1, $2)",
-        id_release, id_watch
+        "INSERT INTO artist_release_watch_alerted (id_release, id_watch, outcome, id_channel, id_message, time_sent)
+         VALUES ($1, $2, $3, $4, $5, NOW())
+         ON CONFLICT ON CONSTRAINT artist_release_watch_alerted_pk DO
+         UPDATE SET outcome=$3, id_channel=$4, id_message=$5, time_sent=NOW(), last_error=NULL, time_next_attempt=NULL",
+        id_release, id_watch, outcome, id_channel.map(|v| v.to_string()), id_message.map(|v| v.to_string())
     )
         .execute(conn)
         .await?;
@@ -123,6 +402,75 @@ pub async fn set_watch_alerted(
     Ok(())
 }
 
+/// Records a failed delivery: `failed` with the time to retry it at, or `dead` once it has run out
+/// of attempts.
+pub async fn record_watch_alert_failure(
+    conn: &PgPool,
+    id_release: &str,
+    id_watch: i32,
+    outcome: &str,
+    id_channel: u64,
+    attempts: i32,
+    last_error: &str,
+    time_next_attempt: Option<DateTime<Utc>>,
+) -> anyhow::Result<()> {
+    query!(
+        "INSERT INTO artist_release_watch_alerted (id_release, id_watch, outcome, id_channel, time_sent, attempts, last_error, time_next_attempt)
+         VALUES ($1, $2, $3, $4, NOW(), $5, $6, $7)
+         ON CONFLICT ON CONSTRAINT artist_release_watch_alerted_pk DO
+         UPDATE SET outcome=$3, id_channel=$4, time_sent=NOW(), attempts=$5, last_error=$6, time_next_attempt=$7",
+        id_release, id_watch, outcome, id_channel.to_string(), attempts, last_error, time_next_attempt
+    )
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
+/// Lists a server's alerts that are waiting to be retried or were dead-lettered, newest first.
+pub async fn list_server_failed_alerts<'a, TDB: PGExec<'a>>(conn: TDB, id_server: u64, limit: i64) -> anyhow::Result<Vec<AlertedRelease>> {
+    let mut stream = query!(
+        "SELECT arwa.id_watch, ar.id_release, a.name AS artist_name, ar.name, ar.album_group, ar.release_date, ar.href,
+                arwa.outcome, arwa.id_channel, arwa.id_message, arwa.time_sent, arwa.attempts, arwa.last_error, arwa.time_next_attempt
+         FROM artist_release_watch_alerted arwa
+         INNER JOIN watch w ON arwa.id_watch = w.id
+         INNER JOIN artist a ON w.id_artist = a.id_artist
+         INNER JOIN artist_release ar ON arwa.id_release = ar.id_release AND w.id_artist = ar.id_artist
+         WHERE w.id_server = $1
+           AND arwa.outcome IN ('failed', 'dead')
+         ORDER BY arwa.time_sent DESC
+         LIMIT $2",
+        id_server.to_string(), limit)
+        .map(|r| map_alerted_release!(r))
+        .fetch(conn);
+
+    let mut result = Vec::new();
+    while let Some(row) = stream.try_next().await? {
+        result.push(row);
+    }
+
+    Ok(result)
+}
+
+/// Moves a server's dead-lettered alerts back into the retry queue with their attempts reset, and
+/// returns how many were moved. Alerts of disabled watches are left dead, since they wouldn't be
+/// sent until the watch is fixed.
+pub async fn replay_server_dead_alerts(conn: &PgPool, id_server: u64) -> anyhow::Result<u64> {
+    let r = query!(
+        "UPDATE artist_release_watch_alerted arwa
+         SET outcome='failed', attempts=0, time_next_attempt=NOW()
+         FROM watch w
+         WHERE arwa.id_watch = w.id
+           AND w.id_server = $1
+           AND w.disabled_reason IS NULL
+           AND arwa.outcome = 'dead'",
+        id_server.to_string())
+        .execute(conn)
+        .await?;
+
+    Ok(r.rows_affected())
+}
+
 pub async fn add_artist_release(
     conn: &PgPool,
     id_release: &str,
@@ -130,6 +478,7 @@ pub async fn add_artist_release(
     artist_ids: Vec<String>,
     artist_names: Vec<String>,
     album_type: &str,
+    album_group: &str,
     available_markets: Vec<String>,
     href: &str,
     image_url: &str,
@@ -138,10 +487,10 @@ pub async fn add_artist_release(
     release_date_precision: &str,
 ) -> anyhow::Result<ArtistRelease> {
     let r = query!(
-        "INSERT INTO artist_release (id_release, id_artist, time_first_seen, artist_ids, artist_names, album_type, available_markets, href, image_url, name, release_date, release_date_precision)
-         VALUES ($1, $2, NOW(), $3, $4, $5, $6, $7, $8, $9, $10, $11)
+        "INSERT INTO artist_release (id_release, id_artist, time_first_seen, artist_ids, artist_names, album_type, album_group, available_markets, href, image_url, name, release_date, release_date_precision)
+         VALUES ($1, $2, NOW(), $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
          RETURNING (time_first_seen)",
-        id_release, id_artist, &artist_ids, &artist_names, album_type, &available_markets, href, image_url, name, release_date, release_date_precision)
+        id_release, id_artist, &artist_ids, &artist_names, album_type, album_group, &available_markets, href, image_url, name, release_date, release_date_precision)
         .fetch_one(conn)
         .await?;
 
@@ -152,6 +501,7 @@ pub async fn add_artist_release(
         artist_ids: artist_ids,
         artist_names: artist_names,
         album_type: album_type.to_owned(),
+        album_group: album_group.to_owned(),
         available_markets: available_markets,
         href: href.to_owned(),
         image_url: image_url.to_owned(),
@@ -161,9 +511,40 @@ pub async fn add_artist_release(
     })
 }
 
-pub async fn list_all_watched_artists<'a, TDB: PGExec<'a>>(conn: TDB) -> anyhow::Result<Vec<String>> {
-    let mut stream = query!("SELECT id_artist FROM vw_all_watched_artists")
-        .map(|r| r.id_artist.unwrap())
+/// Lists an artist's stored releases newest first, optionally limited to an album group and to
+/// releases from a year.
+pub async fn list_artist_releases<'a, TDB: PGExec<'a>>(
+    conn: TDB,
+    id_artist: &str,
+    album_group: Option<&str>,
+    year: Option<i32>,
+    limit: i64,
+    offset: i64,
+) -> anyhow::Result<Vec<ArtistRelease>> {
+    let mut stream = query!(
+        "SELECT *
+         FROM artist_release
+         WHERE id_artist = $1
+           AND ($2::VARCHAR IS NULL OR album_group = $2)
+           AND ($3::VARCHAR IS NULL OR LEFT(release_date, 4) = $3)
+         ORDER BY release_date DESC, time_first_seen DESC
+         LIMIT $4 OFFSET $5",
+        id_artist, album_group, year.map(|v| v.to_string()), limit, offset)
+        .map(|r| ArtistRelease {
+            id_release: r.id_release,
+            id_artist: r.id_artist,
+            time_first_seen: r.time_first_seen,
+            artist_ids: r.artist_ids,
+            artist_names: r.artist_names,
+            album_type: r.album_type,
+            album_group: r.album_group,
+            available_markets: r.available_markets,
+            href: r.href,
+            image_url: r.image_url,
+            name: r.name.unwrap_or_default(),
+            release_date: r.release_date,
+            release_date_precision: r.release_date_precision,
+        })
         .fetch(conn);
 
     let mut result = Vec::new();
@@ -174,8 +555,160 @@ pub async fn list_all_watched_artists<'a, TDB: PGExec<'a>>(conn: TDB) -> anyhow:
     Ok(result)
 }
 
-pub async fn check_known_release(conn: &PgPool, id_release: &str) -> anyhow::Result<bool> {
-    let r = query!("SELECT EXISTS(SELECT 1 FROM artist_release WHERE id_release=$1) AS known", id_release)
+pub async fn count_artist_releases<'a, TDB: PGExec<'a>>(
+    conn: TDB,
+    id_artist: &str,
+    album_group: Option<&str>,
+    year: Option<i32>,
+) -> anyhow::Result<i64> {
+    let r = query!(
+        "SELECT COUNT(*) AS count
+         FROM artist_release
+         WHERE id_artist = $1
+           AND ($2::VARCHAR IS NULL OR album_group = $2)
+           AND ($3::VARCHAR IS NULL OR LEFT(release_date, 4) = $3)",
+        id_artist, album_group, year.map(|v| v.to_string()))
+        .fetch_one(conn)
+        .await?;
+
+    Ok(r.count.unwrap_or(0))
+}
+
+/// Searches the artists with recorded releases that `id_server` watches or `id_user` follows by
+/// name, most followed first. Artists tracked only by other servers or users are never returned.
+pub async fn search_stored_artists<'a, TDB: PGExec<'a>>(
+    conn: TDB,
+    query: &str,
+    id_server: Option<u64>,
+    id_user: u64,
+    limit: i64,
+) -> anyhow::Result<Vec<Artist>> {
+    // `%` and `_` in the query are matched literally, not as wildcards.
+    let pattern = query.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+
+    let mut stream = query!(
+        "SELECT a.*
+         FROM artist a
+         WHERE a.name ILIKE '%' || $1 || '%'
+           AND EXISTS(SELECT 1 FROM artist_release ar WHERE ar.id_artist = a.id_artist)
+           AND (EXISTS(SELECT 1 FROM watch w WHERE w.id_artist = a.id_artist AND w.id_server = $2)
+             OR EXISTS(SELECT 1 FROM user_subscription us WHERE us.id_artist = a.id_artist AND us.id_user = $3))
+         ORDER BY a.followers DESC
+         LIMIT $4",
+        pattern, id_server.map(|v| v.to_string()), id_user.to_string(), limit)
+        .map(|r| Artist {
+            id_artist: r.id_artist,
+            time_updated: r.time_updated,
+            name: r.name,
+            image_url: r.image_url,
+            genres: r.genres,
+            followers: r.followers,
+            popularity: r.popularity,
+        })
+        .fetch(conn);
+
+    let mut result = Vec::new();
+    while let Some(row) = stream.try_next().await? {
+        result.push(row);
+    }
+
+    Ok(result)
+}
+
+/// Gets an artist if `id_server` watches it or `id_user` follows it; see `search_stored_artists`.
+pub async fn get_artist<'a, TDB: PGExec<'a>>(conn: TDB, id_artist: &str, id_server: Option<u64>, id_user: u64) -> anyhow::Result<Option<Artist>> {
+    let r = query!(
+        "SELECT a.*
+         FROM artist a
+         WHERE a.id_artist = $1
+           AND (EXISTS(SELECT 1 FROM watch w WHERE w.id_artist = a.id_artist AND w.id_server = $2)
+             OR EXISTS(SELECT 1 FROM user_subscription us WHERE us.id_artist = a.id_artist AND us.id_user = $3))",
+        id_artist, id_server.map(|v| v.to_string()), id_user.to_string())
+        .map(|r| Artist {
+            id_artist: r.id_artist,
+            time_updated: r.time_updated,
+            name: r.name,
+            image_url: r.image_url,
+            genres: r.genres,
+            followers: r.followers,
+            popularity: r.popularity,
+        })
+        .fetch_optional(conn)
+        .await?;
+
+    Ok(r)
+}
+
+pub async fn list_all_watched_artists<'a, TDB: PGExec<'a>>(conn: TDB) -> anyhow::Result<Vec<ArtistScan>> {
+    let mut stream = query!(
+        "SELECT v.id_artist, v.scan_appears_on, a.appears_on_scanned
+         FROM vw_all_watched_artists v
+         INNER JOIN artist a ON v.id_artist = a.id_artist")
+        .map(|r| ArtistScan {
+            id_artist: r.id_artist.unwrap(),
+            scan_appears_on: r.scan_appears_on.unwrap(),
+            appears_on_scanned: r.appears_on_scanned,
+        })
+        .fetch(conn);
+
+    let mut result = Vec::new();
+    while let Some(row) = stream.try_next().await? {
+        result.push(row);
+    }
+
+    Ok(result)
+}
+
+/// Records the releases an artist is featured on as already alerted, for every watch and
+/// subscription of the artist, once they've been scanned for the first time. Until then the
+/// unalerted views hold them back, so opting into `appears_on` doesn't alert the artist's whole
+/// featured catalogue.
+pub async fn initialize_appears_on(conn: &PgPool, id_artist: &str) -> anyhow::Result<()> {
+    let mut tx = conn.begin().await?;
+
+    query!(
+        "INSERT INTO artist_release_watch_alerted (id_release, id_watch, outcome)
+         SELECT ar.id_release, w.id, 'initial'
+         FROM watch w
+         INNER JOIN artist_release ar ON w.id_artist = ar.id_artist
+         WHERE w.id_artist = $1 AND ar.album_group = 'appears_on'
+         ON CONFLICT DO NOTHING",
+        id_artist)
+        .execute(&mut tx)
+        .await?;
+
+    query!(
+        "INSERT INTO artist_release_subscription_alerted (id_release, id_subscription)
+         SELECT ar.id_release, us.id
+         FROM user_subscription us
+         INNER JOIN artist_release ar ON us.id_artist = ar.id_artist
+         WHERE us.id_artist = $1 AND ar.album_group = 'appears_on'
+         ON CONFLICT DO NOTHING",
+        id_artist)
+        .execute(&mut tx)
+        .await?;
+
+    query!("UPDATE artist SET appears_on_scanned=TRUE WHERE id_artist = $1", id_artist)
+        .execute(&mut tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Marks an artist's featured releases as no longer scanned, once nothing opts into them, so the
+/// next watch or subscription that does starts with a fresh `initialize_appears_on`.
+pub async fn reset_appears_on(conn: &PgPool, id_artist: &str) -> anyhow::Result<()> {
+    query!("UPDATE artist SET appears_on_scanned=FALSE WHERE id_artist = $1", id_artist)
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn check_known_release(conn: &PgPool, id_release: &str, id_artist: &str) -> anyhow::Result<bool> {
+    let r = query!("SELECT EXISTS(SELECT 1 FROM artist_release WHERE id_release=$1 AND id_artist=$2) AS known", id_release, id_artist)
         .fetch_one(conn)
         .await?;
 
@@ -189,3 +722,301 @@ pub async fn check(conn: &PgPool, id_release: &str) -> anyhow::Result<bool> {
 
     Ok(r.known.unwrap())
 }
+
+pub async fn get_guild_settings<'a, TDB: PGExec<'a>>(conn: TDB, id_server: u64) -> anyhow::Result<GuildSettings> {
+    let r = query!(
+        "SELECT *
+         FROM guild_settings
+         WHERE id_server = $1",
+        id_server.to_string())
+        .fetch_optional(conn)
+        .await?;
+
+    Ok(match r {
+        None => GuildSettings {
+            id_server,
+            pause_mode: None,
+            manager_role_ids: Vec::new(),
+            manager_permissions: 0,
+            default_alert_channel: None,
+            default_markets: None,
+            default_album_groups: None,
+            embed_style: "full".to_owned(),
+            link_channel_ids: Vec::new(),
+        },
+        Some(r) => GuildSettings {
+            id_server,
+            pause_mode: r.pause_mode,
+            manager_role_ids: r.manager_role_ids.iter().map(|v| v.parse::<u64>().unwrap()).collect(),
+            manager_permissions: r.manager_permissions as u64,
+            default_alert_channel: r.default_alert_channel.map(|v| v.parse::<u64>().unwrap()),
+            default_markets: r.default_markets,
+            default_album_groups: r.default_album_groups,
+            embed_style: r.embed_style,
+            link_channel_ids: r.link_channel_ids.iter().map(|v| v.parse::<u64>().unwrap()).collect(),
+        },
+    })
+}
+
+pub async fn set_guild_pause_mode(
+    conn: &PgPool,
+    id_server: u64,
+    pause_mode: Option<&str>,
+) -> anyhow::Result<()> {
+    query!(
+        "INSERT INTO guild_settings (id_server, pause_mode)
+         VALUES ($1, $2)
+         ON CONFLICT ON CONSTRAINT guild_settings_pk DO
+         UPDATE SET pause_mode=$2",
+        id_server.to_string(), pause_mode)
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn set_guild_manager_roles(
+    conn: &PgPool,
+    id_server: u64,
+    manager_role_ids: &[u64],
+) -> anyhow::Result<()> {
+    let manager_role_ids = manager_role_ids.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+
+    query!(
+        "INSERT INTO guild_settings (id_server, manager_role_ids)
+         VALUES ($1, $2)
+         ON CONFLICT ON CONSTRAINT guild_settings_pk DO
+         UPDATE SET manager_role_ids=$2",
+        id_server.to_string(), &manager_role_ids)
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn set_guild_manager_permissions(
+    conn: &PgPool,
+    id_server: u64,
+    manager_permissions: u64,
+) -> anyhow::Result<()> {
+    query!(
+        "INSERT INTO guild_settings (id_server, manager_permissions)
+         VALUES ($1, $2)
+         ON CONFLICT ON CONSTRAINT guild_settings_pk DO
+         UPDATE SET manager_permissions=$2",
+        id_server.to_string(), manager_permissions as i64)
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn set_guild_default_alert_channel(
+    conn: &PgPool,
+    id_server: u64,
+    default_alert_channel: Option<u64>,
+) -> anyhow::Result<()> {
+    query!(
+        "INSERT INTO guild_settings (id_server, default_alert_channel)
+         VALUES ($1, $2)
+         ON CONFLICT ON CONSTRAINT guild_settings_pk DO
+         UPDATE SET default_alert_channel=$2",
+        id_server.to_string(), default_alert_channel.map(|v| v.to_string()))
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn set_guild_default_markets(
+    conn: &PgPool,
+    id_server: u64,
+    default_markets: Option<Vec<String>>,
+) -> anyhow::Result<()> {
+    query!(
+        "INSERT INTO guild_settings (id_server, default_markets)
+         VALUES ($1, $2)
+         ON CONFLICT ON CONSTRAINT guild_settings_pk DO
+         UPDATE SET default_markets=$2",
+        id_server.to_string(), default_markets.as_deref())
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn set_guild_default_album_groups(
+    conn: &PgPool,
+    id_server: u64,
+    default_album_groups: Option<Vec<String>>,
+) -> anyhow::Result<()> {
+    query!(
+        "INSERT INTO guild_settings (id_server, default_album_groups)
+         VALUES ($1, $2)
+         ON CONFLICT ON CONSTRAINT guild_settings_pk DO
+         UPDATE SET default_album_groups=$2",
+        id_server.to_string(), default_album_groups.as_deref())
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn set_guild_embed_style(
+    conn: &PgPool,
+    id_server: u64,
+    embed_style: &str,
+) -> anyhow::Result<()> {
+    query!(
+        "INSERT INTO guild_settings (id_server, embed_style)
+         VALUES ($1, $2)
+         ON CONFLICT ON CONSTRAINT guild_settings_pk DO
+         UPDATE SET embed_style=$2",
+        id_server.to_string(), embed_style)
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn set_guild_link_channels(
+    conn: &PgPool,
+    id_server: u64,
+    link_channel_ids: &[u64],
+) -> anyhow::Result<()> {
+    let link_channel_ids = link_channel_ids.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+
+    query!(
+        "INSERT INTO guild_settings (id_server, link_channel_ids)
+         VALUES ($1, $2)
+         ON CONFLICT ON CONSTRAINT guild_settings_pk DO
+         UPDATE SET link_channel_ids=$2",
+        id_server.to_string(), &link_channel_ids)
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
+/// Creates a personal subscription, or returns `None` if the user already follows the artist.
+pub async fn add_subscription<'a, TDB: PGExec<'a>>(
+    conn: TDB,
+    id_user: u64,
+    id_artist: &str,
+    markets: Vec<String>,
+    album_groups: Vec<String>,
+) -> anyhow::Result<Option<UserSubscription>> {
+    let r = query!(
+        "INSERT INTO user_subscription (time_created, id_user, id_artist, markets, album_groups, has_initialized)
+         VALUES (NOW(), $1, $2, $3, $4, FALSE)
+         ON CONFLICT ON CONSTRAINT user_subscription_user_artist_uniq DO NOTHING
+         RETURNING id, time_created",
+        id_user.to_string(), id_artist, &markets, &album_groups)
+        .fetch_optional(conn)
+        .await?;
+
+    Ok(r.map(|r| UserSubscription {
+        id: r.id,
+        time_created: r.time_created,
+        id_user,
+        id_artist: id_artist.to_owned(),
+        markets,
+        album_groups,
+        has_initialized: false,
+        dm_blocked: false,
+    }))
+}
+
+pub async fn list_user_followed_artists<'a, TDB: PGExec<'a>>(conn: TDB, id_user: u64) -> anyhow::Result<Vec<FollowedArtist>> {
+    let mut stream = query!(
+        "SELECT us.id, us.id_artist, a.name
+         FROM user_subscription us
+         INNER JOIN artist a ON us.id_artist = a.id_artist
+         WHERE us.id_user = $1
+         ORDER BY a.name",
+        id_user.to_string())
+        .map(|r| FollowedArtist {
+            id_subscription: r.id,
+            id_artist: r.id_artist,
+            artist_name: r.name,
+        })
+        .fetch(conn);
+
+    let mut result = Vec::new();
+    while let Some(row) = stream.try_next().await? {
+        result.push(row);
+    }
+
+    Ok(result)
+}
+
+pub async fn remove_subscription(conn: &PgPool, id_subscription: i32) -> anyhow::Result<()> {
+    let mut tx = conn.begin().await?;
+
+    query!("DELETE FROM artist_release_subscription_alerted WHERE id_subscription = $1", id_subscription)
+        .execute(&mut tx)
+        .await?;
+
+    query!("DELETE FROM user_subscription WHERE id = $1", id_subscription)
+        .execute(&mut tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+pub async fn list_unalerted_subscriptions<'a, TDB: PGExec<'a>>(conn: TDB) -> anyhow::Result<Vec<PendingSubscriptionAlert>> {
+    let mut stream = query!("SELECT * FROM vw_unalerted_subscriptions")
+        .map(|r| PendingSubscriptionAlert {
+            id_subscription: r.id_subscription.unwrap(),
+            has_initialized: r.has_initialized.unwrap(),
+            id_user: r.id_user.unwrap().parse::<u64>().unwrap(),
+            dm_blocked: r.dm_blocked.unwrap(),
+            matches_filter: r.matches_filter.unwrap(),
+            release: map_pending_release!(r),
+        })
+        .fetch(conn);
+
+    let mut result = Vec::new();
+    while let Some(row) = stream.try_next().await? {
+        result.push(row);
+    }
+
+    Ok(result)
+}
+
+pub async fn set_subscription_alerted(
+    conn: &PgPool,
+    id_release: &str,
+    id_subscription: i32,
+) -> anyhow::Result<()> {
+    query!(
+        "INSERT INTO artist_release_subscription_alerted (id_release, id_subscription)
+         VALUES ($1, $2)",
+        id_release, id_subscription
+    )
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn set_subscription_initialized(conn: &PgPool, id_subscription: i32) -> anyhow::Result<()> {
+    query!("UPDATE user_subscription SET has_initialized=TRUE WHERE id = $1", id_subscription)
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
+/// Flags or clears closed DMs on every subscription of a user.
+pub async fn set_user_dm_blocked(conn: &PgPool, id_user: u64, dm_blocked: bool) -> anyhow::Result<()> {
+    query!(
+        "UPDATE user_subscription SET dm_blocked=$1 WHERE id_user = $2",
+        dm_blocked, id_user.to_string())
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
@@ -1,5 +1,16 @@
 use chrono::{DateTime, Utc};
 
+pub struct Artist {
+    pub id_artist: String,
+    pub time_updated: DateTime<Utc>,
+
+    pub name: String,
+    pub image_url: Option<String>,
+    pub genres: Vec<String>,
+    pub followers: i32,
+    pub popularity: i32,
+}
+
 pub struct Watch {
     pub id: i32,
     pub time_created: DateTime<Utc>,
@@ -8,10 +19,81 @@ pub struct Watch {
     pub id_alert_channel: u64,
 
     pub id_artist: String,
-    pub market: String,
+    pub markets: Vec<String>,
+    pub album_groups: Vec<String>,
 
     pub has_initialized: bool,
     pub time_last_scanned: DateTime<Utc>,
+    /// `skip` or `queue` while the watch is paused.
+    pub pause_mode: Option<String>,
+    /// A role to mention in the watch's alerts.
+    pub id_mention_role: Option<u64>,
+    /// Why the watch stopped sending alerts, set when its channel became unusable.
+    pub disabled_reason: Option<String>,
+}
+
+pub struct GuildSettings {
+    pub id_server: u64,
+
+    /// `skip` or `queue` while every watch in the server is paused.
+    pub pause_mode: Option<String>,
+    /// Roles whose members may manage watches, in addition to administrators.
+    pub manager_role_ids: Vec<u64>,
+    /// Permission bits that let members manage watches, or 0 for none.
+    pub manager_permissions: u64,
+
+    pub default_alert_channel: Option<u64>,
+    pub default_markets: Option<Vec<String>>,
+    pub default_album_groups: Option<Vec<String>>,
+    /// `full` or `compact`; see `crate::monitor::EMBED_STYLE_FULL`.
+    pub embed_style: String,
+    /// Channels where posted Spotify links get a watch prompt; empty while link detection is off.
+    pub link_channel_ids: Vec<u64>,
+}
+
+pub struct WatchedArtist {
+    pub id_watch: i32,
+    pub id_artist: String,
+    pub artist_name: String,
+    pub artist_image_url: Option<String>,
+}
+
+/// A watch disabled because its alert channel can no longer be posted in.
+pub struct DisabledWatch {
+    pub id_watch: i32,
+    pub id_created_by: u64,
+    pub artist_name: String,
+}
+
+pub struct AlertedRelease {
+    pub id_watch: i32,
+    pub id_release: String,
+    pub artist_name: String,
+    pub name: String,
+    pub album_group: String,
+    pub release_date: String,
+    pub href: String,
+
+    /// `sent`, `initial`, `skipped`, `failed` or `dead`; see `crate::monitor::ALERT_SENT`.
+    pub outcome: String,
+    pub id_channel: Option<u64>,
+    /// The alert message, kept so it can be edited or deleted later.
+    pub id_message: Option<u64>,
+    pub time_sent: DateTime<Utc>,
+
+    /// Failed delivery attempts so far.
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    /// When a `failed` alert will next be retried.
+    pub time_next_attempt: Option<DateTime<Utc>>,
+}
+
+pub struct ArtistScan {
+    pub id_artist: String,
+    pub scan_appears_on: bool,
+    /// Whether the releases the artist is featured on are known up to the last scan; see
+    /// `crate::db::model::initialize_appears_on`.
+    pub appears_on_scanned: bool,
 }
 
 pub struct ArtistRelease {
@@ -22,6 +104,7 @@ pub struct ArtistRelease {
     pub artist_ids: Vec<String>,
     pub artist_names: Vec<String>,
     pub album_type: String,
+    pub album_group: String,
     pub available_markets: Vec<String>,
     pub href: String,
     pub image_url: String,
@@ -30,18 +113,64 @@ pub struct ArtistRelease {
     pub release_date_precision: String,
 }
 
-pub struct PendingWatchAlert {
-    pub id_watch: i32,
+pub struct UserSubscription {
+    pub id: i32,
+    pub time_created: DateTime<Utc>,
+    pub id_user: u64,
+
+    pub id_artist: String,
+    pub markets: Vec<String>,
+    pub album_groups: Vec<String>,
+
     pub has_initialized: bool,
-    pub id_server: u64,
-    pub id_alert_channel: u64,
-    pub market: String,
+    /// Set when the user's DMs were closed on the last delivery attempt; cleared by `/follow`.
+    pub dm_blocked: bool,
+}
+
+pub struct FollowedArtist {
+    pub id_subscription: i32,
+    pub id_artist: String,
+    pub artist_name: String,
+}
+
+/// The release half of a pending alert, shared by channel and DM deliveries.
+pub struct PendingRelease {
+    pub artist_name: String,
+    pub artist_image_url: Option<String>,
 
     pub id_release: String,
+    pub id_artist: String,
     pub artist_names: Vec<String>,
     pub album_type: String,
+    pub album_group: String,
     pub href: String,
     pub image_url: String,
     pub name: String,
     pub release_date: String,
 }
+
+pub struct PendingWatchAlert {
+    pub id_watch: i32,
+    pub has_initialized: bool,
+    pub id_server: u64,
+    pub id_alert_channel: u64,
+    pub markets: Vec<String>,
+    pub id_mention_role: Option<u64>,
+    pub pause_mode: Option<String>,
+    pub embed_style: String,
+    pub matches_filter: bool,
+    /// Failed delivery attempts so far.
+    pub attempts: i32,
+
+    pub release: PendingRelease,
+}
+
+pub struct PendingSubscriptionAlert {
+    pub id_subscription: i32,
+    pub has_initialized: bool,
+    pub id_user: u64,
+    pub dm_blocked: bool,
+    pub matches_filter: bool,
+
+    pub release: PendingRelease,
+}
@@ -2,17 +2,25 @@ use std::collections::HashMap;
 use std::sync::Arc;
 
 use evlog::meta;
+use serde_json::{json, Value};
 use serenity::async_trait;
+use serenity::builder::CreateApplicationCommand;
+use serenity::cache::Cache;
 use serenity::client::{Context, EventHandler};
 use serenity::http::Http;
+use serenity::model::channel::Message;
 use serenity::model::guild::Guild;
 use serenity::model::id::GuildId;
 use serenity::model::interactions::{Interaction, InteractionResponseType, InteractionType};
+use serenity::model::interactions::application_command::{ApplicationCommand, ApplicationCommandInteraction};
+use serenity::model::interactions::autocomplete::AutocompleteInteraction;
+use serenity::model::interactions::message_component::MessageComponentInteraction;
 use serenity::prelude::TypeMapKey;
 use tokio::sync::Mutex;
 
 use crate::commands;
 use crate::db::dbclient::DBClient;
+use crate::helpers::command_def::CommandDef;
 use crate::runtime::get_logger;
 use crate::spotify::SpotifyClient;
 
@@ -20,10 +28,13 @@ pub struct BotData {
     pub db_client: Arc<DBClient>,
     pub spotify_client: Arc<SpotifyClient>,
     pub http: Arc<Mutex<Option<Arc<Http>>>>,
+    pub cache: Arc<Mutex<Option<Arc<Cache>>>>,
+    /// Whether the bot receives message content, without which link detection can't see links.
+    pub message_content: bool,
 }
 
 impl BotData {
-    pub async fn new(db_client: DBClient, spotify_client: SpotifyClient) -> Self {
+    pub async fn new(db_client: DBClient, spotify_client: SpotifyClient, max_delivery_attempts: i32, message_content: bool) -> Self {
         let db_client = Arc::new(db_client);
         let spotify_client = Arc::new(spotify_client);
 
@@ -35,20 +46,24 @@ impl BotData {
         // let watch_map = Arc::new(watch_map);
 
         let http = Arc::new(Mutex::new(None));
+        let cache = Arc::new(Mutex::new(None));
 
         let db_client_ref = db_client.clone();
         let spotify_client_ref = spotify_client.clone();
         // let watch_map_ref = watch_map.clone();
         let http_ref = http.clone();
+        let cache_ref = cache.clone();
 
         tokio::spawn(async move {
-            crate::monitor::worker(db_client_ref, spotify_client_ref, http_ref).await;
+            crate::monitor::worker(db_client_ref, spotify_client_ref, http_ref, cache_ref, max_delivery_attempts).await;
         });
 
         Self {
             db_client,
             spotify_client,
             http,
+            cache,
+            message_content,
         }
     }
 }
@@ -65,6 +80,11 @@ impl EventHandler for BotHandler {
         let mut data = ctx.data.write().await;
         let bot_data = data.get_mut::<BotData>().unwrap();
 
+        // The worker starts once the HTTP client is set, so the cache has to be set first.
+        let mut cache_ref = bot_data.cache.lock().await;
+        let _ = cache_ref.insert(ctx.cache.clone());
+        drop(cache_ref);
+
         let mut http_ref = bot_data.http.lock().await;
         let _ = http_ref.insert(ctx.http.clone());
     }
@@ -96,8 +116,10 @@ impl EventHandler for BotHandler {
                 continue;
             }
 
-            if existing_map.contains_key(cmd.name) {
-                if !cmd.re_register {
+            // Discord limits how many commands can be created per day, so unchanged commands
+            // aren't registered again.
+            if let Some(existing) = existing_map.get(cmd.name) {
+                if !cmd.re_register && !command_changed(existing, cmd) {
                     get_logger().debug("Command already registered in this server.", meta! {
                         "GuildID" => guild.id,
                         "GuildName" => guild.name,
@@ -107,64 +129,174 @@ impl EventHandler for BotHandler {
                 }
             }
 
-            let created = guild.create_application_command(&ctx.http, |c| {
+            match guild.create_application_command(&ctx.http, |c| {
                 (cmd.builder)(c)
-            }).await.unwrap();
+            }).await {
+                Ok(created) => {
+                    get_logger().debug("Registered command in server.", meta! {
+                        "GuildID" => guild.id,
+                        "GuildName" => guild.name,
+                        "Command" => cmd.name,
+                        "ID" => created.id
+                    });
+                }
+                Err(e) => {
+                    get_logger().error("Failed to register command in server.", meta! {
+                        "GuildID" => guild.id,
+                        "GuildName" => guild.name,
+                        "Command" => cmd.name,
+                        "Error" => e,
+                    });
+                }
+            }
+        }
+    }
 
-            get_logger().debug("Registered command in server.", meta! {
-                "GuildID" => guild.id,
-                "GuildName" => guild.name,
-                "Command" => cmd.name,
-                "ID" => created.id
+    async fn message(&self, ctx: Context, message: Message) {
+        let guild_id = message.guild_id;
+        let message_id = message.id;
+
+        if let Err(e) = commands::detect_links(ctx, message).await {
+            get_logger().error("Error occurred in link detection.", meta! {
+                "GuildID" => guild_id,
+                "MessageID" => message_id,
+                "Error" => e,
             });
         }
     }
 
     async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
-        if let Interaction::ApplicationCommand(interaction) = interaction {
-            let guild = ctx.cache.guild(interaction.guild_id.unwrap()).await.unwrap();
+        match interaction {
+            Interaction::ApplicationCommand(interaction) => self.application_command(ctx, interaction).await,
+            Interaction::Autocomplete(interaction) => self.autocomplete(ctx, interaction).await,
+            Interaction::MessageComponent(interaction) => self.message_component(ctx, interaction).await,
+            _ => {}
+        }
+    }
+}
+
+impl BotHandler {
+    async fn application_command(&self, ctx: Context, interaction: ApplicationCommandInteraction) {
+        let guild = ctx.cache.guild(interaction.guild_id.unwrap()).unwrap();
 
-            if interaction.kind == InteractionType::Ping {
-                get_logger().info("Interaction ping.", meta! {
+        if interaction.kind == InteractionType::Ping {
+            get_logger().info("Interaction ping.", meta! {
+                "GuildID" => guild.id,
+                "GuildName" => guild.name,
+                "InteractionID" => interaction.id
+            });
+
+            interaction.create_interaction_response(ctx.http.as_ref(), |r| {
+                r.kind(InteractionResponseType::Pong)
+            }).await.unwrap();
+        } else if interaction.kind == InteractionType::ApplicationCommand {
+            get_logger().info("Interaction ping.", meta! {
                     "GuildID" => guild.id,
                     "GuildName" => guild.name,
-                    "InteractionID" => interaction.id
+                    "InteractionID" => interaction.id,
+                    "CommandID" => interaction.data.id,
+                    "CommandName" => interaction.data.name
                 });
 
-                interaction.create_interaction_response(ctx.http.as_ref(), |r| {
-                    r.kind(InteractionResponseType::Pong)
-                }).await.unwrap();
-            } else if interaction.kind == InteractionType::ApplicationCommand {
-                get_logger().info("Interaction ping.", meta! {
+            let handler = commands::get_handler(&interaction.data.name);
+            if handler.is_none() { return; }
+
+            let interaction_id = interaction.id;
+            let command_id = interaction.data.id.clone();
+            let command_name = interaction.data.name.clone();
+
+            let r: anyhow::Result<()> = handler.unwrap()(ctx, interaction).await;
+            match r {
+                Ok(()) => {}
+                Err(e) => {
+                    get_logger().error("Error occurred in interaction processor.", meta! {
                         "GuildID" => guild.id,
                         "GuildName" => guild.name,
-                        "InteractionID" => interaction.id,
-                        "CommandID" => interaction.data.id,
-                        "CommandName" => interaction.data.name
+                        "InteractionID" => interaction_id,
+                        "CommandID" => command_id,
+                        "CommandName" => command_name,
+                        "Error" => e,
                     });
-
-                let handler = commands::get_handler(&interaction.data.name);
-                if handler.is_none() { return; }
-
-                let interaction_id = interaction.id;
-                let command_id = interaction.data.id.clone();
-                let command_name = interaction.data.name.clone();
-
-                let r: anyhow::Result<()> = handler.unwrap()(ctx, interaction).await;
-                match r {
-                    Ok(()) => {}
-                    Err(e) => {
-                        get_logger().error("Error occurred in interaction processor.", meta! {
-                            "GuildID" => guild.id,
-                            "GuildName" => guild.name,
-                            "InteractionID" => interaction_id,
-                            "CommandID" => command_id,
-                            "CommandName" => command_name,
-                            "Error" => e,
-                        });
-                    }
                 }
             }
         }
     }
+
+    async fn autocomplete(&self, ctx: Context, interaction: AutocompleteInteraction) {
+        let guild = ctx.cache.guild(interaction.guild_id.unwrap()).unwrap();
+
+        let handler = match commands::get_autocomplete_handler(&interaction.data.name) {
+            None => return,
+            Some(v) => v,
+        };
+
+        let interaction_id = interaction.id;
+        let command_name = interaction.data.name.clone();
+
+        let r: anyhow::Result<()> = handler(ctx, interaction).await;
+        if let Err(e) = r {
+            get_logger().error("Error occurred in autocomplete processor.", meta! {
+                "GuildID" => guild.id,
+                "GuildName" => guild.name,
+                "InteractionID" => interaction_id,
+                "CommandName" => command_name,
+                "Error" => e,
+            });
+        }
+    }
+
+    async fn message_component(&self, ctx: Context, interaction: MessageComponentInteraction) {
+        get_logger().debug("Component interaction.", meta! {
+            "GuildID" => interaction.guild_id,
+            "InteractionID" => interaction.id,
+            "CustomID" => interaction.data.custom_id
+        });
+
+        let handler = match commands::get_component_handler(&interaction.data.custom_id) {
+            None => return,
+            Some(v) => v,
+        };
+
+        let guild_id = interaction.guild_id;
+        let interaction_id = interaction.id;
+        let custom_id = interaction.data.custom_id.clone();
+
+        let r: anyhow::Result<()> = handler(ctx, interaction).await;
+        if let Err(e) = r {
+            get_logger().error("Error occurred in component processor.", meta! {
+                "GuildID" => guild_id,
+                "InteractionID" => interaction_id,
+                "CustomID" => custom_id,
+                "Error" => e,
+            });
+        }
+    }
+}
+
+/// Returns whether a registered command's description or options differ from its definition.
+fn command_changed(existing: &ApplicationCommand, cmd: &CommandDef) -> bool {
+    let mut definition = CreateApplicationCommand::default();
+    (cmd.builder)(&mut definition);
+
+    let defined_options = definition.0.get("options").cloned().unwrap_or_else(|| json!([]));
+    let existing_options = serde_json::to_value(&existing.options).unwrap_or_else(|_| json!([]));
+
+    definition.0.get("description").and_then(Value::as_str).unwrap_or_default() != existing.description
+        || normalize_options(&defined_options) != normalize_options(&existing_options)
+}
+
+/// Keeps the option fields both the builder and Discord's response have, with Discord's defaults
+/// filled in, so the two can be compared.
+fn normalize_options(options: &Value) -> Vec<Value> {
+    options.as_array().map_or_else(Vec::new, |v| v.iter()
+        .map(|v| json!({
+            "type": v["type"],
+            "name": v["name"],
+            "description": v["description"],
+            "required": v["required"].as_bool().unwrap_or(false),
+            "choices": v["choices"].as_array().cloned().unwrap_or_default(),
+            "channel_types": v["channel_types"].as_array().cloned().unwrap_or_default(),
+            "options": normalize_options(&v["options"]),
+        }))
+        .collect())
 }
@@ -1,6 +1,8 @@
 use serenity::builder::CreateApplicationCommand;
 use serenity::futures::future::BoxFuture;
 use serenity::model::interactions::application_command::ApplicationCommandInteraction;
+use serenity::model::interactions::autocomplete::AutocompleteInteraction;
+use serenity::model::interactions::message_component::MessageComponentInteraction;
 use serenity::model::prelude::*;
 use serenity::prelude::*;
 
@@ -10,11 +12,23 @@ use serenity::prelude::*;
 pub type InteractionResult = BoxFuture<'static, anyhow::Result<()>>;
 pub type CommandBuilder = fn(&mut CreateApplicationCommand) -> &mut CreateApplicationCommand;
 pub type InteractionHandler = fn(Context, ApplicationCommandInteraction) -> InteractionResult;
+pub type AutocompleteHandler = fn(Context, AutocompleteInteraction) -> InteractionResult;
+pub type ComponentHandler = fn(Context, MessageComponentInteraction) -> InteractionResult;
 
 pub struct CommandDef {
     pub name: &'static str,
     pub builder: CommandBuilder,
     pub handler: InteractionHandler,
+    pub autocomplete: Option<AutocompleteHandler>,
+    /// Registers the command on every start, even when it doesn't look changed. Changes to an
+    /// option's autocomplete flag aren't detected, since Discord doesn't return it.
     pub re_register: bool,
     pub whitelisted_servers: Option<&'static [GuildId]>,
 }
+
+/// Routes message component interactions (buttons, select menus) by the part of their custom ID
+/// before the first ':'; everything after it is state for the handler to parse.
+pub struct ComponentDef {
+    pub prefix: &'static str,
+    pub handler: ComponentHandler,
+}
@@ -1,14 +1,42 @@
 use serenity::client::Context;
+use serenity::model::channel::{Attachment, PartialChannel};
+use serenity::model::guild::Role;
 use serenity::model::id::UserId;
-use serenity::model::interactions::application_command::{ApplicationCommandInteraction, ApplicationCommandInteractionDataOption};
+use serenity::model::interactions::application_command::{ApplicationCommandInteraction, ApplicationCommandInteractionDataOption, ApplicationCommandOptionType};
 use serenity::model::interactions::application_command::ApplicationCommandInteractionDataOptionValue;
 
 use crate::helpers::command_resp;
 
+/// Returns the options passed to the invoked command, descending into the selected subcommand
+/// (and subcommand group) if there is one.
+#[must_use]
+pub fn command_options(interaction: &ApplicationCommandInteraction) -> &[ApplicationCommandInteractionDataOption] {
+    innermost_options(&interaction.data.options)
+}
+
+fn innermost_options(opts: &[ApplicationCommandInteractionDataOption]) -> &[ApplicationCommandInteractionDataOption] {
+    match opts.first() {
+        Some(v) if v.kind == ApplicationCommandOptionType::SubCommand || v.kind == ApplicationCommandOptionType::SubCommandGroup => innermost_options(&v.options),
+        _ => opts,
+    }
+}
+
+/// Returns the name of the invoked subcommand, if any.
+#[must_use]
+pub fn find_subcommand(interaction: &ApplicationCommandInteraction) -> Option<&str> {
+    let opt = interaction.data.options.first()?;
+
+    match opt.kind {
+        ApplicationCommandOptionType::SubCommand => Some(&opt.name),
+        ApplicationCommandOptionType::SubCommandGroup => opt.options.first().map(|v| v.name.as_str()),
+        _ => None,
+    }
+}
+
 pub async fn find_required<T, F>(ctx: &Context, interaction: &ApplicationCommandInteraction, getter: F, name: &str) -> anyhow::Result<Option<T>>
     where F: Fn(&[ApplicationCommandInteractionDataOption], &str) -> Option<T>
 {
-    let v = getter(&interaction.data.options, name);
+    let v = getter(command_options(interaction), name);
     match v {
         None => {
             command_resp::reply(ctx, &interaction, |x| x.content(format!("{} is required", name))).await?;
@@ -70,6 +98,36 @@ pub fn find_user_opt(opts: &[ApplicationCommandInteractionDataOption], name: &st
     });
 }
 
+#[must_use]
+pub fn find_channel_opt(opts: &[ApplicationCommandInteractionDataOption], name: &str) -> Option<PartialChannel> {
+    let i = opts.iter().find(|v| v.name == name)?;
+
+    match &i.resolved {
+        Some(ApplicationCommandInteractionDataOptionValue::Channel(x)) => Some(x.clone()),
+        _ => None,
+    }
+}
+
+#[must_use]
+pub fn find_role_opt(opts: &[ApplicationCommandInteractionDataOption], name: &str) -> Option<Role> {
+    let i = opts.iter().find(|v| v.name == name)?;
+
+    match &i.resolved {
+        Some(ApplicationCommandInteractionDataOptionValue::Role(x)) => Some(x.clone()),
+        _ => None,
+    }
+}
+
+#[must_use]
+pub fn find_attachment_opt(opts: &[ApplicationCommandInteractionDataOption], name: &str) -> Option<Attachment> {
+    let i = opts.iter().find(|v| v.name == name)?;
+
+    match &i.resolved {
+        Some(ApplicationCommandInteractionDataOptionValue::Attachment(x)) => Some(x.clone()),
+        _ => None,
+    }
+}
+
 #[must_use]
 pub fn find_integer_opt(opts: &[ApplicationCommandInteractionDataOption], name: &str) -> Option<i64> {
     let i = opts.iter().find(|v| v.name == name)?;
@@ -89,3 +147,24 @@ pub fn find_boolean_opt(opts: &[ApplicationCommandInteractionDataOption], name:
         _ => None,
     }
 }
+
+pub struct FocusedOptResult {
+    pub name: String,
+    pub value: String,
+}
+
+#[must_use]
+pub fn find_focused_opt(opts: &[ApplicationCommandInteractionDataOption]) -> Option<FocusedOptResult> {
+    let i = innermost_options(opts).iter().find(|v| v.focused)?;
+
+    // Autocomplete interactions don't populate `resolved`; the partial input is only in `value`.
+    let value = match &i.value {
+        Some(v) => v.as_str().unwrap_or_default().to_owned(),
+        None => String::new(),
+    };
+
+    Some(FocusedOptResult {
+        name: i.name.clone(),
+        value,
+    })
+}
@@ -3,6 +3,7 @@ use serenity::client::Context;
 use serenity::model::id::MessageId;
 use serenity::model::interactions::InteractionResponseType;
 use serenity::model::interactions::application_command::ApplicationCommandInteraction;
+use serenity::model::interactions::message_component::MessageComponentInteraction;
 
 pub async fn reply<T>(ctx: &Context, interaction: &ApplicationCommandInteraction, content: T) -> anyhow::Result<()>
     where T: FnOnce(&mut CreateInteractionResponseData) -> &mut CreateInteractionResponseData,
@@ -59,3 +60,15 @@ pub async fn delete_deferred_result(ctx: &Context, interaction: &ApplicationComm
     interaction.delete_original_interaction_response(&ctx).await?;
     Ok(())
 }
+
+pub async fn update_message<T>(ctx: &Context, interaction: &MessageComponentInteraction, content: T) -> anyhow::Result<()>
+    where T: FnOnce(&mut CreateInteractionResponseData) -> &mut CreateInteractionResponseData,
+{
+    interaction.create_interaction_response(&ctx, |response| {
+        response.kind(InteractionResponseType::UpdateMessage);
+        response.interaction_response_data(content);
+        response
+    }).await?;
+
+    Ok(())
+}
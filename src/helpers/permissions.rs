@@ -0,0 +1,205 @@
+use evlog::meta;
+use serenity::cache::Cache;
+use serenity::client::Context;
+use serenity::model::channel::ChannelType;
+use serenity::model::guild::Member;
+use serenity::model::id::{ChannelId, GuildId, RoleId};
+use serenity::model::interactions::application_command::ApplicationCommandInteraction;
+use serenity::model::Permissions;
+
+use crate::db;
+use crate::db::schema::GuildSettings;
+use crate::handler::BotData;
+use crate::helpers::command_resp;
+use crate::runtime::get_logger;
+
+/// Channel types alerts can be delivered to.
+///
+/// Forum channels are left out: they don't accept messages, only new posts, so every alert would
+/// have to open its own titled thread, and the follow button, alert history links and the
+/// unusable-channel checks would all need a forum-specific path. Serenity 0.11.2 also has no
+/// builder for forum posts.
+pub const ALERT_CHANNEL_TYPES: &[ChannelType] = &[ChannelType::Text, ChannelType::News];
+
+/// Permissions a server can let manage watches in addition to administrators, as the option
+/// value, display name and permission.
+pub const MANAGER_PERMISSIONS: &[(&str, &str, Permissions)] = &[
+    ("manage_guild", "Manage Server", Permissions::MANAGE_GUILD),
+    ("manage_channels", "Manage Channels", Permissions::MANAGE_CHANNELS),
+    ("manage_roles", "Manage Roles", Permissions::MANAGE_ROLES),
+    ("manage_messages", "Manage Messages", Permissions::MANAGE_MESSAGES),
+];
+
+/// Permissions the bot needs in an alert channel, with their display names in Discord.
+const ALERT_CHANNEL_PERMISSIONS: &[(&str, Permissions)] = &[
+    ("View Channel", Permissions::VIEW_CHANNEL),
+    ("Send Messages", Permissions::SEND_MESSAGES),
+    ("Embed Links", Permissions::EMBED_LINKS),
+];
+
+pub enum AlertChannelError {
+    NotFound,
+    WrongType,
+    MissingPermissions(Permissions),
+    /// The bot's permissions couldn't be worked out from the cache, for example while the server's
+    /// members are still loading.
+    PermissionsUnknown,
+}
+
+impl AlertChannelError {
+    #[must_use]
+    pub fn description(&self, channel_id: ChannelId) -> String {
+        match self {
+            AlertChannelError::NotFound => format!(
+                "I can't see the channel <#{}>; is it in this server?", channel_id
+            ),
+            AlertChannelError::WrongType => format!(
+                "Alerts can only be sent to text or announcement channels, and <#{}> is neither.", channel_id
+            ),
+            AlertChannelError::MissingPermissions(missing) => format!(
+                "I'm missing these permissions in <#{}>: {}.", channel_id, permission_names(*missing)
+            ),
+            AlertChannelError::PermissionsUnknown => format!(
+                "I couldn't check my permissions in <#{}>; try again in a moment.", channel_id
+            ),
+        }
+    }
+
+    /// Describes the problem for the message telling a server its watches were disabled.
+    #[must_use]
+    pub fn disabled_reason(&self) -> String {
+        match self {
+            AlertChannelError::NotFound => "the channel was deleted".to_owned(),
+            AlertChannelError::WrongType => "the channel is no longer a text or announcement channel".to_owned(),
+            AlertChannelError::MissingPermissions(missing) => format!(
+                "I'm missing these permissions there: {}", permission_names(*missing)
+            ),
+            AlertChannelError::PermissionsUnknown => "I couldn't check my permissions there".to_owned(),
+        }
+    }
+}
+
+/// Lists the display names of the alert channel permissions in `missing`.
+fn permission_names(missing: Permissions) -> String {
+    ALERT_CHANNEL_PERMISSIONS.iter()
+        .filter(|(_, permission)| missing.contains(*permission))
+        .map(|(name, _)| *name)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Checks, against the cached guild and channel state, that the bot can post alert embeds in
+/// `channel_id`.
+pub fn check_alert_channel(cache: &Cache, guild_id: GuildId, channel_id: ChannelId) -> Result<(), AlertChannelError> {
+    let channel = match cache.guild_channel(channel_id) {
+        None => return Err(AlertChannelError::NotFound),
+        Some(v) => v,
+    };
+
+    if channel.guild_id != guild_id {
+        return Err(AlertChannelError::NotFound);
+    }
+
+    if !ALERT_CHANNEL_TYPES.contains(&channel.kind) {
+        return Err(AlertChannelError::WrongType);
+    }
+
+    let required = ALERT_CHANNEL_PERMISSIONS.iter().fold(Permissions::empty(), |acc, (_, v)| acc | *v);
+    let granted = channel.permissions_for_user(cache, cache.current_user_id())
+        .map_err(|_| AlertChannelError::PermissionsUnknown)?;
+
+    if !granted.contains(required) {
+        return Err(AlertChannelError::MissingPermissions(required - granted));
+    }
+
+    Ok(())
+}
+
+/// Returns whether a member may manage the server's watches: administrators always can, as can
+/// members with one of the server's manager roles or its manager permission.
+#[must_use]
+pub fn can_manage_watches(settings: &GuildSettings, permissions: Permissions, roles: &[RoleId]) -> bool {
+    if permissions.contains(Permissions::ADMINISTRATOR) {
+        return true;
+    }
+
+    let manager_permissions = Permissions::from_bits_truncate(settings.manager_permissions);
+    if !manager_permissions.is_empty() && permissions.contains(manager_permissions) {
+        return true;
+    }
+
+    roles.iter().any(|v| settings.manager_role_ids.contains(v.as_u64()))
+}
+
+/// Describes who may manage the server's watches, for permission errors and /settings.
+#[must_use]
+pub fn describe_managers(settings: &GuildSettings) -> String {
+    let mut managers = vec!["members with the 'Administrator' permission".to_owned()];
+
+    if let Some((_, name, _)) = MANAGER_PERMISSIONS.iter().find(|(_, _, v)| v.bits() == settings.manager_permissions) {
+        managers.push(format!("members with the '{}' permission", name));
+    }
+
+    if !settings.manager_role_ids.is_empty() {
+        managers.push(format!("members with the {} role", settings.manager_role_ids.iter()
+            .map(|v| format!("<@&{}>", v))
+            .collect::<Vec<_>>()
+            .join(" or ")));
+    }
+
+    match managers.len() {
+        1 => managers.remove(0),
+        _ => {
+            let last = managers.pop().unwrap();
+            format!("{} and {}", managers.join(", "), last)
+        }
+    }
+}
+
+/// Returns why a member may not manage the server's watches, or `None` if they may.
+pub async fn check_watch_manager(ctx: &Context, guild_id: GuildId, member: &Member, command: &str) -> anyhow::Result<Option<String>> {
+    let permissions = match member.permissions {
+        None => return Ok(Some("Could not read your permissions in this server.".to_owned())),
+        Some(v) => v,
+    };
+
+    let data = ctx.data.read().await;
+    let data = data.get::<BotData>().unwrap();
+
+    let settings = db::model::get_guild_settings(data.db_client.conn(), *guild_id.as_u64()).await?;
+
+    if !can_manage_watches(&settings, permissions, &member.roles) {
+        return Ok(Some(format!("Only {} may use {}.", describe_managers(&settings), command)));
+    }
+
+    Ok(None)
+}
+
+/// Checks that a watch-management command was used in a server, by a member allowed to manage
+/// watches there. If not, replies to the deferred interaction and returns `None`.
+pub async fn authorize_watch_manager(ctx: &Context, interaction: &ApplicationCommandInteraction, command: &str) -> anyhow::Result<Option<GuildId>> {
+    let guild_id = match interaction.guild_id {
+        None => {
+            get_logger().info("Interaction was not used in a guild.", meta! {
+                "InteractionID" => interaction.id,
+            });
+            command_resp::reply_deferred_result(ctx, interaction, format!("{} can only be used in a server.", command)).await.unwrap();
+            return Ok(None);
+        }
+        Some(v) => v,
+    };
+
+    let member = interaction.member.as_ref().unwrap();
+
+    if let Some(reason) = check_watch_manager(ctx, guild_id, member, command).await? {
+        get_logger().info("Non-manager attempted to manage watches.", meta! {
+            "InteractionID" => interaction.id,
+            "GuildID" => guild_id,
+            "Command" => command,
+        });
+        command_resp::reply_deferred_result(ctx, interaction, reason).await.unwrap();
+        return Ok(None);
+    }
+
+    Ok(Some(guild_id))
+}
@@ -5,6 +5,7 @@ use std::env;
 use evlog::{LogEventConsolePrinter, Logger};
 use rspotify::{ClientCredsSpotify, Credentials};
 use serenity::Client;
+use serenity::model::gateway::GatewayIntents;
 
 use crate::db::dbclient::DBClient;
 use crate::handler::{BotData, BotHandler};
@@ -30,6 +31,14 @@ async fn main() {
     let db_url = env::var("DATABASE_URL").expect("expected DATABASE_URL");
     let spotify_id = env::var("SPOTIFY_ID").expect("expected SPOTIFY_ID");
     let spotify_secret = env::var("SPOTIFY_SECRET").expect("expected SPOTIFY_SECRET");
+    let max_delivery_attempts: i32 = match env::var("SPOTLIT_MAX_DELIVERY_ATTEMPTS") {
+        Ok(v) => v.parse().expect("max delivery attempts is invalid"),
+        Err(_) => monitor::DEFAULT_MAX_DELIVERY_ATTEMPTS,
+    };
+    let message_content: bool = match env::var("SPOTLIT_MESSAGE_CONTENT") {
+        Ok(v) => v.parse().expect("message content flag is invalid"),
+        Err(_) => false,
+    };
 
     let mut spotify = ClientCredsSpotify::new(Credentials::new(&spotify_id, &spotify_secret));
     spotify.request_token().await.unwrap();
@@ -55,9 +64,16 @@ async fn main() {
     let spotify_client = SpotifyClient::new(&spotify_id, &spotify_secret).await
         .expect("failed to connect to Spotify");
 
-    let data = handler::BotData::new(db_client, spotify_client).await;
+    let data = handler::BotData::new(db_client, spotify_client, max_delivery_attempts, message_content).await;
 
-    let mut client = Client::builder(&token)
+    // Message content is a privileged intent; link detection needs it to see posted links, but
+    // Discord refuses to connect a bot that requests it without it being enabled for the application.
+    let mut intents = GatewayIntents::GUILDS | GatewayIntents::GUILD_MESSAGES;
+    if message_content {
+        intents |= GatewayIntents::MESSAGE_CONTENT;
+    }
+
+    let mut client = Client::builder(&token, intents)
         .event_handler(BotHandler {})
         .application_id(appl)
         .await
@@ -1,21 +1,78 @@
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
-use chrono::Utc;
+use chrono::{Duration, Utc};
 
 use evlog::meta;
-use rspotify::clients::BaseClient;
-use rspotify::model::{ArtistId, Id};
-use serenity::http::Http;
-use serenity::model::prelude::ChannelId;
+use rspotify::model::{FullArtist, Id, SimplifiedAlbum};
+use serenity::builder::CreateEmbed;
+use serenity::cache::Cache;
+use serenity::http::{Http, HttpError};
+use serenity::model::interactions::message_component::ButtonStyle;
+use serenity::model::prelude::{ChannelId, GuildId, RoleId, UserId};
 use serenity::utils::Color;
 use tokio::sync::Mutex;
 use tokio::time::Instant;
 
 use crate::db;
 use crate::db::dbclient::DBClient;
+use crate::db::schema::{Artist, ArtistScan, DisabledWatch, PendingRelease, PendingWatchAlert};
+use crate::helpers::permissions;
+use crate::helpers::permissions::AlertChannelError;
 use crate::runtime::get_logger;
+use crate::spotify::release_types;
 use crate::spotify::SpotifyClient;
 
-pub async fn worker(db: Arc<DBClient>, spotify: Arc<SpotifyClient>, http_ref: Arc<Mutex<Option<Arc<Http>>>>) {
+/// The maximum page size of the artist albums endpoint.
+const SCAN_PAGE_SIZE: u32 = 50;
+
+/// Releases found while paused are marked as alerted without being sent.
+pub const PAUSE_MODE_SKIP: &str = "skip";
+/// Releases found while paused are held back by `vw_unalerted_watches` and sent on resume.
+pub const PAUSE_MODE_QUEUE: &str = "queue";
+
+/// Alerts with the release's cover art as the embed image and a field per detail.
+pub const EMBED_STYLE_FULL: &str = "full";
+/// Alerts with the cover art as a thumbnail and the details on one line.
+pub const EMBED_STYLE_COMPACT: &str = "compact";
+
+/// Alert outcomes recorded in `artist_release_watch_alerted`: delivered, recorded without being sent
+/// on a watch's first scan, dropped by a pause or the watch's release types, failed to deliver and
+/// waiting to be retried, or given up on after too many failures.
+pub const ALERT_SENT: &str = "sent";
+pub const ALERT_INITIAL: &str = "initial";
+pub const ALERT_SKIPPED: &str = "skipped";
+pub const ALERT_FAILED: &str = "failed";
+pub const ALERT_DEAD: &str = "dead";
+
+/// Failed deliveries an alert gets before it's dead-lettered, unless `SPOTLIT_MAX_DELIVERY_ATTEMPTS`
+/// is set.
+pub const DEFAULT_MAX_DELIVERY_ATTEMPTS: i32 = 8;
+
+/// The wait before retrying an alert's first failed delivery; it doubles with each further failure,
+/// up to `MAX_RETRY_DELAY_MINS`.
+const BASE_RETRY_DELAY_MINS: i64 = 3;
+const MAX_RETRY_DELAY_MINS: i64 = 12 * 60;
+
+/// Custom ID prefix of the button on channel alerts that subscribes the clicking member to DM
+/// alerts; the full ID is `follow:<artist ID>`.
+pub const FOLLOW_BUTTON: &str = "follow";
+
+/// Discord's "Cannot send messages to this user" error, returned when a user's DMs are closed.
+const DISCORD_CANNOT_DM_USER: isize = 50007;
+
+/// Discord errors that retrying won't fix until someone changes the watch or the channel.
+const DISCORD_UNKNOWN_CHANNEL: isize = 10003;
+const DISCORD_UNKNOWN_GUILD: isize = 10004;
+const DISCORD_MISSING_ACCESS: isize = 50001;
+/// "Cannot send an empty message", returned when the alert's embed was dropped for lack of the
+/// Embed Links permission and there was no role ping to send instead.
+const DISCORD_CANNOT_SEND_EMPTY_MESSAGE: isize = 50006;
+const DISCORD_MISSING_PERMISSIONS: isize = 50013;
+
+/// Artists listed by name in a disabled watch notice before the rest are only counted.
+const MAX_LISTED_DISABLED_ARTISTS: usize = 20;
+
+pub async fn worker(db: Arc<DBClient>, spotify: Arc<SpotifyClient>, http_ref: Arc<Mutex<Option<Arc<Http>>>>, cache_ref: Arc<Mutex<Option<Arc<Cache>>>>, max_delivery_attempts: i32) {
     loop {
         let http_opt = http_ref.lock().await;
         match http_opt.as_ref() {
@@ -33,7 +90,7 @@ pub async fn worker(db: Arc<DBClient>, spotify: Arc<SpotifyClient>, http_ref: Ar
     loop {
         let start = Instant::now();
 
-        let artist_ids = match db::model::list_all_watched_artists(db.conn()).await {
+        let artists = match db::model::list_all_watched_artists(db.conn()).await {
             Ok(v) => v,
             Err(e) => {
                 get_logger().error("Failed to list watched artists in database.", meta! {
@@ -45,23 +102,38 @@ pub async fn worker(db: Arc<DBClient>, spotify: Arc<SpotifyClient>, http_ref: Ar
             }
         };
 
-        for artist_id in artist_ids {
-            match scan_artist(&db, &spotify, &artist_id).await {
+        match refresh_artists(&db, &spotify).await {
+            Ok(count) => {
+                if count > 0 {
+                    get_logger().info("Refreshed artist metadata.", meta! {
+                        "Count" => count,
+                    });
+                }
+            }
+            Err(e) => {
+                get_logger().error("Failed to refresh artist metadata.", meta! {
+                    "Error" => e,
+                });
+            }
+        }
+
+        for artist in artists {
+            match scan_artist(&db, &spotify, &artist).await {
                 Ok(()) => {
                     get_logger().info("Scanned artist.", meta! {
-                        "ID" => artist_id,
+                        "ID" => artist.id_artist,
                     });
                 }
                 Err(e) => {
                     get_logger().error("Failed to update artist.", meta! {
                         "Error" => e,
-                        "ArtistID" => artist_id,
+                        "ArtistID" => artist.id_artist,
                     });
                 }
             }
         }
 
-        match process_unalerted_watches(&db, &http_ref).await {
+        match process_unalerted_watches(&db, &http_ref, &cache_ref, max_delivery_attempts).await {
             Ok(_) => {
                 get_logger().info("Successfully processed unalerted watches.", None);
             }
@@ -75,6 +147,12 @@ pub async fn worker(db: Arc<DBClient>, spotify: Arc<SpotifyClient>, http_ref: Ar
             }
         }
 
+        if let Err(e) = process_unalerted_subscriptions(&db, &http_ref).await {
+            get_logger().error("Failed to process unalerted subscriptions.", meta! {
+                "Error" => e,
+            });
+        }
+
         //
 
         let elapsed_secs = start.elapsed().as_secs() as i32;
@@ -95,105 +173,249 @@ pub async fn worker(db: Arc<DBClient>, spotify: Arc<SpotifyClient>, http_ref: Ar
     }
 }
 
-async fn scan_artist(db: &Arc<DBClient>, spotify: &Arc<SpotifyClient>, id: &str) -> anyhow::Result<()> {
-    spotify.conn().auto_reauth().await?;
+pub async fn save_artist(db: &DBClient, artist: &FullArtist) -> anyhow::Result<Artist> {
+    db::model::upsert_artist(
+        db.conn(),
+        artist.id.id(),
+        &artist.name,
+        artist.images.first().map(|v| v.url.as_str()),
+        artist.genres.clone(),
+        artist.followers.total as i32,
+        artist.popularity as i32,
+    ).await
+}
 
-    let artist = ArtistId::from_id(id)?;
+async fn refresh_artists(db: &Arc<DBClient>, spotify: &Arc<SpotifyClient>) -> anyhow::Result<usize> {
+    let stale = db::model::list_stale_artists(db.conn(), Utc::now() - Duration::hours(24)).await?;
+    if stale.is_empty() {
+        return Ok(0);
+    }
 
-    let albums = spotify.conn().artist_albums_manual(
-        &artist,
-        None,
-        None,
-        Some(20),
-        None,
-    ).await?;
+    for artist in spotify.artists(&stale).await? {
+        save_artist(db, &artist).await?;
+    }
 
-    for album in albums.items {
-        match &album.id {
-            None => {
-                get_logger().warn("Ignoring release with no release ID.", meta! {
-                    "ArtistID" => id,
-                    "ReleaseName" => album.name,
-                });
-            }
-            Some(v) => {
-                if db::model::check_known_release(db.conn(), v.id()).await? {
-                    continue;
+    Ok(stale.len())
+}
+
+/// Records new releases of an artist. Every album group is requested at once, newest-first within
+/// each group, and a group stops being recorded once one of its releases is already known, so most
+/// artists take a single request. Groups that run past the first page without reaching a known
+/// release are then paged through on their own. Releases the artist is only featured on are
+/// scanned while some watch or subscription opts into `appears_on`; the first complete scan of
+/// them records them as already alerted, since they were never scanned before and would all look
+/// new.
+async fn scan_artist(db: &Arc<DBClient>, spotify: &Arc<SpotifyClient>, artist: &ArtistScan) -> anyhow::Result<()> {
+    let id = artist.id_artist.as_str();
+
+    let mut groups = vec!["album", "single", "compilation"];
+    if artist.scan_appears_on {
+        groups.push("appears_on");
+    }
+
+    let page = spotify.artist_albums(id, &groups, SCAN_PAGE_SIZE, 0).await?;
+    let has_next = page.next.is_some();
+
+    let mut seen: HashMap<String, u32> = HashMap::new();
+    let mut reached_known: HashSet<String> = HashSet::new();
+
+    for album in page.items {
+        let group = album.album_group.clone().or_else(|| album.album_type.clone()).unwrap_or_default();
+
+        *seen.entry(group.clone()).or_insert(0) += 1;
+        if reached_known.contains(&group) {
+            continue;
+        }
+
+        if save_release(db, id, album).await? {
+            reached_known.insert(group);
+        }
+    }
+
+    if has_next {
+        for group in groups.into_iter().filter(|v| !reached_known.contains(*v)) {
+            // Continue after the group's releases that were on the first page.
+            let mut offset = seen.get(group).copied().unwrap_or(0);
+
+            loop {
+                let page = spotify.artist_albums(id, &[group], SCAN_PAGE_SIZE, offset).await?;
+
+                let count = page.items.len() as u32;
+                let mut reached_known = false;
+
+                for album in page.items {
+                    reached_known |= save_release(db, id, album).await?;
                 }
 
-                get_logger().warn("Found new release.", meta! {
-                    "ArtistID" => id,
-                    "ReleaseName" => album.name,
-                    "ReleaseID" => v,
-                });
+                if reached_known || page.next.is_none() || count == 0 {
+                    break;
+                }
 
-                let artist_ids = album.artists.iter()
-                    .map(|v| v.id.as_ref().unwrap().id().to_owned()).collect::<Vec<String>>();
-                let artist_names = album.artists.iter()
-                    .map(|v| v.name.clone()).collect::<Vec<String>>();
-
-                let image_url = album.images.first().unwrap().clone().url;
-
-                db::model::add_artist_release(
-                    db.conn(),
-                    v.id(),
-                    id,
-                    artist_ids,
-                    artist_names,
-                    album.album_type.as_ref().unwrap(),
-                    album.available_markets,
-                    album.href.as_ref().unwrap(),
-                    &image_url,
-                    &album.name,
-                    album.release_date.as_ref().unwrap(),
-                    album.release_date_precision.as_ref().unwrap(),
-                ).await?;
+                offset += count;
             }
         }
     }
 
+    if artist.scan_appears_on && !artist.appears_on_scanned {
+        get_logger().info("Recorded artist's featured releases on first scan.", meta! {
+            "ArtistID" => id,
+        });
+
+        db::model::initialize_appears_on(db.conn(), id).await?;
+    } else if !artist.scan_appears_on && artist.appears_on_scanned {
+        db::model::reset_appears_on(db.conn(), id).await?;
+    }
+
     Ok(())
 }
 
-async fn process_unalerted_watches(db: &Arc<DBClient>, http_ref: &Arc<Mutex<Option<Arc<Http>>>>) -> anyhow::Result<()> {
+/// Saves a release if it isn't known yet, and returns whether it was already known.
+async fn save_release(db: &Arc<DBClient>, id: &str, album: SimplifiedAlbum) -> anyhow::Result<bool> {
+    let v = match &album.id {
+        None => {
+            get_logger().warn("Ignoring release with no release ID.", meta! {
+                "ArtistID" => id,
+                "ReleaseName" => album.name,
+            });
+            return Ok(false);
+        }
+        Some(v) => v,
+    };
+
+    if db::model::check_known_release(db.conn(), v.id(), id).await? {
+        return Ok(true);
+    }
+
+    get_logger().warn("Found new release.", meta! {
+        "ArtistID" => id,
+        "ReleaseName" => album.name,
+        "ReleaseID" => v,
+    });
+
+    let artist_ids = album.artists.iter()
+        .map(|v| v.id.as_ref().unwrap().id().to_owned()).collect::<Vec<String>>();
+    let artist_names = album.artists.iter()
+        .map(|v| v.name.clone()).collect::<Vec<String>>();
+
+    let image_url = album.images.first().unwrap().clone().url;
+
+    db::model::add_artist_release(
+        db.conn(),
+        v.id(),
+        id,
+        artist_ids,
+        artist_names,
+        album.album_type.as_ref().unwrap(),
+        album.album_group.as_ref().or_else(|| album.album_type.as_ref()).unwrap(),
+        album.available_markets,
+        album.href.as_ref().unwrap(),
+        &image_url,
+        &album.name,
+        album.release_date.as_ref().unwrap(),
+        album.release_date_precision.as_ref().unwrap(),
+    ).await?;
+
+    Ok(false)
+}
+
+/// Sends pending channel alerts. Failed deliveries are retried with exponential backoff by
+/// `vw_unalerted_watches`, and dead-lettered after `max_delivery_attempts` failures until replayed
+/// with `/alerts replay`. When a channel can no longer be posted in, every watch sending alerts
+/// there is disabled instead, and the failed alert is kept to be sent once the watch is fixed.
+async fn process_unalerted_watches(db: &Arc<DBClient>, http_ref: &Arc<Mutex<Option<Arc<Http>>>>, cache_ref: &Arc<Mutex<Option<Arc<Cache>>>>, max_delivery_attempts: i32) -> anyhow::Result<()> {
     let unalerted_watches = db::model::list_unalerted_watches(db.conn()).await?;
 
     let http = http_ref.lock().await;
     let http = http.as_ref().unwrap();
 
+    let cache = cache_ref.lock().await;
+    let cache = cache.as_ref().unwrap();
+
+    // Servers and channels whose watches were disabled during this run.
+    let mut dead_channels: HashSet<(u64, u64)> = HashSet::new();
+
     for a in &unalerted_watches {
+        if dead_channels.contains(&(a.id_server, a.id_alert_channel)) {
+            continue;
+        }
+
         if !a.has_initialized {
-            db::model::set_watch_alerted(db.conn(), &a.id_release, a.id_watch).await?;
+            db::model::record_watch_alert(db.conn(), &a.release.id_release, a.id_watch, ALERT_INITIAL, None, None).await?;
             db::model::update_watch(db.conn(), a.id_watch, true, Utc::now()).await?;
             continue;
         }
 
+        if a.pause_mode.as_deref() == Some(PAUSE_MODE_SKIP) {
+            get_logger().debug("Skipping release found while watch is paused.", meta! {
+                "WatchID" => a.id_watch,
+                "ReleaseID" => a.release.id_release,
+            });
+
+            db::model::record_watch_alert(db.conn(), &a.release.id_release, a.id_watch, ALERT_SKIPPED, None, None).await?;
+            continue;
+        }
+
+        if !a.matches_filter {
+            get_logger().debug("Skipping release excluded by watch's release types.", meta! {
+                "WatchID" => a.id_watch,
+                "ReleaseID" => a.release.id_release,
+                "AlbumGroup" => a.release.album_group,
+            });
+
+            db::model::record_watch_alert(db.conn(), &a.release.id_release, a.id_watch, ALERT_SKIPPED, None, None).await?;
+            continue;
+        }
+
         let channel = ChannelId(a.id_alert_channel);
 
-        let r = channel.send_message(http, |c| {
-            c.embed(|e| {
-                e.author(|a| {
-                    a.name("Spotlit");
-                    a.icon_url("https://i.imgur.com/iKUvWHR.png");
+        // Discord drops the embed instead of failing when Embed Links is missing, so the channel is
+        // checked first. Servers missing from the cache are unavailable or gone, and are left to the
+        // error codes below.
+        let unusable = match cache.guild_field(a.id_server, |g| g.id) {
+            None => None,
+            Some(guild_id) => match permissions::check_alert_channel(cache, guild_id, channel) {
+                // Permissions that can't be worked out yet are left to the send attempt.
+                Ok(()) | Err(AlertChannelError::PermissionsUnknown) => None,
+                Err(e) => Some(e),
+            },
+        };
 
-                    a
-                });
+        if let Some(e) = unusable {
+            let reason = e.disabled_reason();
 
-                e.title(format!("New Spotify release: {}", a.name));
-                e.image(a.image_url.clone());
-                e.url(a.href.clone());
-                e.color(Color::from_rgb(30, 215, 96));
-                e.field("Artists", a.artist_names.join(", "), false);
-                e.field("Type", match a.album_type.as_ref() {
-                    "album" => "Album",
-                    "single" => "Single/EP",
-                    "compilation" => "Compilation",
-                    other => other,
-                }, true);
-                e.field("Released", a.release_date.clone(), true);
-                e
+            get_logger().warn("Alert channel is unusable; disabling its watches.", meta! {
+                "WatchID" => a.id_watch,
+                "GuildID" => a.id_server,
+                "ChannelID" => a.id_alert_channel,
+                "Reason" => reason,
             });
 
+            disable_channel_watches(db, http, a, &reason, &e.description(channel)).await?;
+            dead_channels.insert((a.id_server, a.id_alert_channel));
+            continue;
+        }
+
+        let r = channel.send_message(http, |c| {
+            // Only the watch's role may be pinged, whatever the release name contains.
+            if let Some(role) = a.id_mention_role {
+                c.content(format!("<@&{}>", role));
+            }
+            c.allowed_mentions(|m| {
+                m.empty_parse();
+                if let Some(role) = a.id_mention_role {
+                    m.roles(vec![RoleId(role)]);
+                }
+
+                m
+            });
+
+            c.embed(|e| build_alert_embed(e, &a.release, &a.embed_style));
+            c.components(|c| c.create_action_row(|r| r
+                .create_button(|b| b
+                    .custom_id(format!("{}:{}", FOLLOW_BUTTON, a.release.id_artist))
+                    .label("Notify me too")
+                    .style(ButtonStyle::Secondary))));
+
             c
         }).await;
 
@@ -201,19 +423,254 @@ async fn process_unalerted_watches(db: &Arc<DBClient>, http_ref: &Arc<Mutex<Opti
             Ok(v) => {
                 get_logger().info("Successfully sent watch alert.", meta! {
                     "WatchID" => a.id_watch,
-                    "ReleaseID" => a.id_release,
-                    "ReleaseName" => a.name,
+                    "ReleaseID" => a.release.id_release,
+                    "ReleaseName" => a.release.name,
                     "MessageID" => v.id,
                 });
 
-                db::model::set_watch_alerted(db.conn(), &a.id_release, a.id_watch).await?;
+                db::model::record_watch_alert(db.conn(), &a.release.id_release, a.id_watch, ALERT_SENT, Some(a.id_alert_channel), Some(*v.id.as_u64())).await?;
                 db::model::update_watch(db.conn(), a.id_watch, true, Utc::now()).await?;
             }
             Err(e) => {
-                get_logger().error("Failed to send watch alert.", meta! {
-                    "WatchID" => a.id_watch,
-                    "ReleaseID" => a.id_release,
-                    "ReleaseName" => a.name,
+                let error = e.to_string();
+
+                if let Some(reason) = discord_error_code(&e).and_then(permanent_failure_reason) {
+                    get_logger().warn("Alert channel is unusable; disabling its watches.", meta! {
+                        "WatchID" => a.id_watch,
+                        "GuildID" => a.id_server,
+                        "ChannelID" => a.id_alert_channel,
+                        "Error" => e,
+                    });
+
+                    disable_channel_watches(db, http, a, reason, &error).await?;
+                    dead_channels.insert((a.id_server, a.id_alert_channel));
+                    continue;
+                }
+
+                let attempts = a.attempts + 1;
+
+                if attempts >= max_delivery_attempts {
+                    get_logger().error("Failed to send watch alert; giving up.", meta! {
+                        "WatchID" => a.id_watch,
+                        "ReleaseID" => a.release.id_release,
+                        "ReleaseName" => a.release.name,
+                        "Attempts" => attempts,
+                        "Error" => e,
+                    });
+
+                    db::model::record_watch_alert_failure(
+                        db.conn(), &a.release.id_release, a.id_watch, ALERT_DEAD, a.id_alert_channel, attempts, &error, None,
+                    ).await?;
+                } else {
+                    let time_next_attempt = Utc::now() + retry_delay(attempts);
+
+                    get_logger().error("Failed to send watch alert; will retry.", meta! {
+                        "WatchID" => a.id_watch,
+                        "ReleaseID" => a.release.id_release,
+                        "ReleaseName" => a.release.name,
+                        "Attempts" => attempts,
+                        "NextAttempt" => time_next_attempt.to_rfc3339(),
+                        "Error" => e,
+                    });
+
+                    db::model::record_watch_alert_failure(
+                        db.conn(), &a.release.id_release, a.id_watch, ALERT_FAILED, a.id_alert_channel, attempts, &error, Some(time_next_attempt),
+                    ).await?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Describes a Discord error that won't go away by retrying, for the message telling the server its
+/// watch was disabled.
+fn permanent_failure_reason(code: isize) -> Option<&'static str> {
+    match code {
+        DISCORD_UNKNOWN_CHANNEL => Some("the channel was deleted"),
+        DISCORD_UNKNOWN_GUILD => Some("I'm no longer in the server"),
+        DISCORD_MISSING_ACCESS => Some("I can no longer see the channel"),
+        DISCORD_CANNOT_SEND_EMPTY_MESSAGE => Some("I'm missing the Embed Links permission there"),
+        DISCORD_MISSING_PERMISSIONS => Some("I'm missing the Send Messages or Embed Links permission there"),
+        _ => None,
+    }
+}
+
+/// Records an alert whose channel can no longer be posted in, disables every watch of the server
+/// that sends alerts to that channel, and tells the server and the watches' creators.
+async fn disable_channel_watches(db: &Arc<DBClient>, http: &Arc<Http>, a: &PendingWatchAlert, reason: &str, error: &str) -> anyhow::Result<()> {
+    // Not counted as an attempt; the alert is retried as soon as the watch is fixed.
+    db::model::record_watch_alert_failure(
+        db.conn(), &a.release.id_release, a.id_watch, ALERT_FAILED, a.id_alert_channel, a.attempts, error, Some(Utc::now()),
+    ).await?;
+
+    let disabled = db::model::disable_channel_watches(db.conn(), a.id_server, a.id_alert_channel, reason).await?;
+
+    get_logger().info("Disabled watches of unusable alert channel.", meta! {
+        "GuildID" => a.id_server,
+        "ChannelID" => a.id_alert_channel,
+        "Count" => disabled.len(),
+    });
+
+    notify_watches_disabled(http, a.id_server, a.id_alert_channel, &disabled, reason).await;
+
+    Ok(())
+}
+
+/// Tells the server in its system channel, and each creator by DM, which watches of a channel were
+/// disabled and how to fix them; one message each. Failures are only logged, since either may be
+/// unreachable too.
+async fn notify_watches_disabled(http: &Arc<Http>, id_server: u64, id_alert_channel: u64, watches: &[DisabledWatch], reason: &str) {
+    if watches.is_empty() {
+        return;
+    }
+
+    let guild = GuildId(id_server).to_partial_guild(http).await.ok();
+
+    let content = |watches: Vec<&DisabledWatch>| {
+        let mut artists = watches.iter()
+            .take(MAX_LISTED_DISABLED_ARTISTS)
+            .map(|v| format!("**{}**", v.artist_name))
+            .collect::<Vec<_>>()
+            .join(", ");
+        if watches.len() > MAX_LISTED_DISABLED_ARTISTS {
+            artists.push_str(&format!(" and {} more", watches.len() - MAX_LISTED_DISABLED_ARTISTS));
+        }
+
+        format!(
+            "I've stopped sending release alerts for {} to <#{}>{} because {}. \
+             Once that's fixed, run `/watch edit` with each artist, optionally picking a new `channel`, to turn its alerts back on; \
+             alerts missed in the meantime are sent then. To remove a watch instead, run `/unmonitor`.",
+            artists,
+            id_alert_channel,
+            guild.as_ref().map_or(String::new(), |g| format!(" in **{}**", g.name)),
+            reason,
+        )
+    };
+
+    let system_channel = guild.as_ref()
+        .and_then(|g| g.system_channel_id)
+        .filter(|v| *v.as_u64() != id_alert_channel);
+
+    if let Some(channel) = system_channel {
+        if let Err(e) = channel.send_message(http, |c| c
+            .content(content(watches.iter().collect()))
+            .allowed_mentions(|m| m.empty_parse())).await {
+            get_logger().warn("Failed to notify server of disabled watches.", meta! {
+                "GuildID" => id_server,
+                "ChannelID" => channel,
+                "Error" => e,
+            });
+        }
+    }
+
+    let mut by_creator: HashMap<u64, Vec<&DisabledWatch>> = HashMap::new();
+    for watch in watches {
+        by_creator.entry(watch.id_created_by).or_default().push(watch);
+    }
+
+    for (id_created_by, watches) in by_creator {
+        let message = content(watches);
+
+        let r = match UserId(id_created_by).create_dm_channel(http).await {
+            Ok(dm) => dm.id.send_message(http, |c| c
+                .content(&message)
+                .allowed_mentions(|m| m.empty_parse())).await.map(|_| ()),
+            Err(e) => Err(e),
+        };
+
+        if let Err(e) = r {
+            get_logger().warn("Failed to notify creator of disabled watches.", meta! {
+                "GuildID" => id_server,
+                "UserID" => id_created_by,
+                "Error" => e,
+            });
+        }
+    }
+}
+
+/// The wait before retrying an alert that has failed `attempts` times.
+fn retry_delay(attempts: i32) -> Duration {
+    let exponent = (attempts - 1).clamp(0, 16) as u32;
+
+    Duration::minutes((BASE_RETRY_DELAY_MINS * 2_i64.pow(exponent)).min(MAX_RETRY_DELAY_MINS))
+}
+
+/// Sends personal subscription alerts by DM. Users whose DMs are closed are flagged, and their
+/// alerts are dropped rather than retried until they run `/follow` again.
+async fn process_unalerted_subscriptions(db: &Arc<DBClient>, http_ref: &Arc<Mutex<Option<Arc<Http>>>>) -> anyhow::Result<()> {
+    let unalerted_subscriptions = db::model::list_unalerted_subscriptions(db.conn()).await?;
+
+    let http = http_ref.lock().await;
+    let http = http.as_ref().unwrap();
+
+    let mut dm_channels: HashMap<u64, ChannelId> = HashMap::new();
+    let mut dm_blocked: HashSet<u64> = HashSet::new();
+
+    for a in &unalerted_subscriptions {
+        if !a.has_initialized {
+            db::model::set_subscription_alerted(db.conn(), &a.release.id_release, a.id_subscription).await?;
+            db::model::set_subscription_initialized(db.conn(), a.id_subscription).await?;
+            continue;
+        }
+
+        if !a.matches_filter || a.dm_blocked || dm_blocked.contains(&a.id_user) {
+            db::model::set_subscription_alerted(db.conn(), &a.release.id_release, a.id_subscription).await?;
+            continue;
+        }
+
+        let channel = match dm_channels.get(&a.id_user) {
+            Some(v) => *v,
+            None => match UserId(a.id_user).create_dm_channel(http).await {
+                Ok(v) => {
+                    dm_channels.insert(a.id_user, v.id);
+                    v.id
+                }
+                Err(e) => {
+                    get_logger().error("Failed to open DM channel for subscription alert.", meta! {
+                        "SubscriptionID" => a.id_subscription,
+                        "UserID" => a.id_user,
+                        "Error" => e,
+                    });
+                    continue;
+                }
+            },
+        };
+
+        let r = channel.send_message(http, |c| {
+            c.allowed_mentions(|m| m.empty_parse());
+            c.embed(|e| build_alert_embed(e, &a.release, EMBED_STYLE_FULL));
+
+            c
+        }).await;
+
+        match r {
+            Ok(v) => {
+                get_logger().info("Successfully sent subscription alert.", meta! {
+                    "SubscriptionID" => a.id_subscription,
+                    "ReleaseID" => a.release.id_release,
+                    "ReleaseName" => a.release.name,
+                    "MessageID" => v.id,
+                });
+
+                db::model::set_subscription_alerted(db.conn(), &a.release.id_release, a.id_subscription).await?;
+            }
+            Err(e) if discord_error_code(&e) == Some(DISCORD_CANNOT_DM_USER) => {
+                get_logger().info("User has closed DMs; pausing their subscription alerts.", meta! {
+                    "SubscriptionID" => a.id_subscription,
+                    "UserID" => a.id_user,
+                });
+
+                dm_blocked.insert(a.id_user);
+                db::model::set_user_dm_blocked(db.conn(), a.id_user, true).await?;
+                db::model::set_subscription_alerted(db.conn(), &a.release.id_release, a.id_subscription).await?;
+            }
+            Err(e) => {
+                get_logger().error("Failed to send subscription alert.", meta! {
+                    "SubscriptionID" => a.id_subscription,
+                    "ReleaseID" => a.release.id_release,
+                    "ReleaseName" => a.release.name,
                     "Error" => e,
                 });
             }
@@ -222,3 +679,77 @@ async fn process_unalerted_watches(db: &Arc<DBClient>, http_ref: &Arc<Mutex<Opti
 
     Ok(())
 }
+
+fn build_alert_embed<'a>(e: &'a mut CreateEmbed, r: &PendingRelease, embed_style: &str) -> &'a mut CreateEmbed {
+    e.author(|a| {
+        a.name("Spotlit");
+        a.icon_url("https://i.imgur.com/iKUvWHR.png");
+
+        a
+    });
+
+    if r.album_group == "appears_on" {
+        e.title(format!("New Spotify feature: {}", r.name));
+    } else {
+        e.title(format!("New Spotify release: {}", r.name));
+    }
+    e.url(r.href.clone());
+    e.color(Color::from_rgb(30, 215, 96));
+
+    if embed_style == EMBED_STYLE_COMPACT {
+        e.thumbnail(r.image_url.clone());
+        e.description(format!(
+            "{} by {} · Released {}",
+            release_types::display_name(&r.album_type), r.artist_names.join(", "), r.release_date,
+        ));
+        return e;
+    }
+
+    if r.album_group == "appears_on" {
+        e.description(format!("**{}** is featured on this release.", r.artist_name));
+    }
+    e.image(r.image_url.clone());
+    if let Some(artist_image_url) = &r.artist_image_url {
+        e.thumbnail(artist_image_url);
+    }
+    e.field("Artists", r.artist_names.join(", "), false);
+    e.field("Type", release_types::display_name(&r.album_type), true);
+    e.field("Released", r.release_date.clone(), true);
+    e
+}
+
+/// Returns the JSON error code of a failed Discord API request.
+fn discord_error_code(e: &serenity::Error) -> Option<isize> {
+    match e {
+        serenity::Error::Http(e) => match e.as_ref() {
+            HttpError::UnsuccessfulRequest(r) => Some(r.error.code),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retry_delay_doubles_from_the_base_delay() {
+        assert_eq!(retry_delay(1), Duration::minutes(3));
+        assert_eq!(retry_delay(2), Duration::minutes(6));
+        assert_eq!(retry_delay(5), Duration::minutes(48));
+    }
+
+    #[test]
+    fn retry_delay_is_capped() {
+        assert_eq!(retry_delay(8), Duration::minutes(384));
+        assert_eq!(retry_delay(9), Duration::minutes(MAX_RETRY_DELAY_MINS));
+        assert_eq!(retry_delay(1000), Duration::minutes(MAX_RETRY_DELAY_MINS));
+    }
+
+    #[test]
+    fn retry_delay_treats_non_positive_attempts_as_the_first() {
+        assert_eq!(retry_delay(0), Duration::minutes(BASE_RETRY_DELAY_MINS));
+        assert_eq!(retry_delay(-3), Duration::minutes(BASE_RETRY_DELAY_MINS));
+    }
+}
@@ -0,0 +1,161 @@
+/// ISO 3166-1 alpha-2 codes of the countries Spotify is available in, as returned by the
+/// `/markets` endpoint.
+pub const MARKETS: &[&str] = &[
+    "AD", "AE", "AG", "AL", "AM", "AO", "AR", "AT", "AU", "AZ", "BA", "BB", "BD", "BE", "BF", "BG",
+    "BH", "BI", "BJ", "BN", "BO", "BR", "BS", "BT", "BW", "BY", "BZ", "CA", "CD", "CG", "CH", "CI",
+    "CL", "CM", "CO", "CR", "CV", "CW", "CY", "CZ", "DE", "DJ", "DK", "DM", "DO", "DZ", "EC", "EE",
+    "EG", "ES", "ET", "FI", "FJ", "FM", "FR", "GA", "GB", "GD", "GE", "GH", "GM", "GN", "GQ", "GR",
+    "GT", "GW", "GY", "HK", "HN", "HR", "HT", "HU", "ID", "IE", "IL", "IN", "IQ", "IS", "IT", "JM",
+    "JO", "JP", "KE", "KG", "KH", "KI", "KM", "KN", "KR", "KW", "KZ", "LA", "LB", "LC", "LI", "LK",
+    "LR", "LS", "LT", "LU", "LV", "LY", "MA", "MC", "MD", "ME", "MG", "MH", "MK", "ML", "MN", "MO",
+    "MR", "MT", "MU", "MV", "MW", "MX", "MY", "MZ", "NA", "NE", "NG", "NI", "NL", "NO", "NP", "NR",
+    "NZ", "OM", "PA", "PE", "PG", "PH", "PK", "PL", "PR", "PS", "PT", "PW", "PY", "QA", "RO", "RS",
+    "RW", "SA", "SB", "SC", "SE", "SG", "SI", "SK", "SL", "SM", "SN", "SR", "ST", "SV", "SZ", "TD",
+    "TG", "TH", "TJ", "TL", "TN", "TO", "TR", "TT", "TV", "TW", "TZ", "UA", "UG", "US", "UY", "UZ",
+    "VC", "VE", "VN", "VU", "WS", "XK", "ZA", "ZM", "ZW",
+];
+
+/// Named groups of markets that can be used anywhere a market code is accepted.
+pub const PRESETS: &[(&str, &[&str])] = &[
+    ("EU", &[
+        "AT", "BE", "BG", "CY", "CZ", "DE", "DK", "EE", "ES", "FI", "FR", "GR", "HR", "HU", "IE", "IT",
+        "LT", "LU", "LV", "MT", "NL", "PL", "PT", "RO", "SE", "SI", "SK",
+    ]),
+    ("LATAM", &[
+        "AR", "BO", "BR", "CL", "CO", "CR", "DO", "EC", "GT", "HN", "MX", "NI", "PA", "PE", "PR", "PY",
+        "SV", "UY", "VE",
+    ]),
+    ("NORDICS", &["DK", "FI", "IS", "NO", "SE"]),
+    ("DACH", &["AT", "CH", "DE"]),
+    ("BENELUX", &["BE", "LU", "NL"]),
+];
+
+/// Countries for Discord locales that don't include one.
+const LOCALE_MARKETS: &[(&str, &str)] = &[
+    ("bg", "BG"), ("cs", "CZ"), ("da", "DK"), ("de", "DE"), ("el", "GR"), ("fi", "FI"),
+    ("fr", "FR"), ("he", "IL"), ("hi", "IN"), ("hr", "HR"), ("hu", "HU"), ("id", "ID"),
+    ("it", "IT"), ("ja", "JP"), ("ko", "KR"), ("lt", "LT"), ("nl", "NL"), ("no", "NO"),
+    ("pl", "PL"), ("ro", "RO"), ("th", "TH"), ("tr", "TR"), ("uk", "UA"), ("vi", "VN"),
+];
+
+/// Parses a comma- or space-separated list of market codes and preset names into a sorted,
+/// de-duplicated list of market codes. On failure, returns a message for the user naming the
+/// invalid entries.
+pub fn parse_markets(input: &str) -> Result<Vec<String>, String> {
+    let mut markets = Vec::new();
+    let mut invalid = Vec::new();
+
+    for token in input.split(|c: char| c == ',' || c.is_whitespace()).filter(|v| !v.is_empty()) {
+        let token = token.to_uppercase();
+
+        if let Some((_, preset)) = PRESETS.iter().find(|(name, _)| *name == token) {
+            markets.extend(preset.iter().map(|v| v.to_string()));
+        } else if MARKETS.contains(&token.as_str()) {
+            markets.push(token);
+        } else {
+            invalid.push(token);
+        }
+    }
+
+    if !invalid.is_empty() {
+        return Err(format!(
+            "{} {} not a market Spotify supports; use 2-letter country codes like `US` or `GB`, or one of these presets: {}.",
+            invalid.iter().map(|v| format!("`{}`", v)).collect::<Vec<_>>().join(", "),
+            if invalid.len() == 1 { "is" } else { "are" },
+            PRESETS.iter().map(|(name, _)| format!("`{}`", name)).collect::<Vec<_>>().join(", "),
+        ));
+    }
+
+    if markets.is_empty() {
+        return Err("At least one market is required, like `US` or `GB`.".to_owned());
+    }
+
+    markets.sort();
+    markets.dedup();
+
+    Ok(markets)
+}
+
+/// Formats a market list for display, collapsing it to a preset name when it matches one exactly.
+#[must_use]
+pub fn describe_markets(markets: &[String]) -> String {
+    for (name, preset) in PRESETS {
+        if preset.len() == markets.len() && preset.iter().all(|v| markets.iter().any(|m| m == v)) {
+            return name.to_string();
+        }
+    }
+
+    markets.join(", ")
+}
+
+/// Picks the market for a Discord locale like `en-US` or `fr`, if Spotify is available in its
+/// country.
+#[must_use]
+pub fn market_from_locale(locale: &str) -> Option<String> {
+    let market = match locale.split_once('-') {
+        Some((_, region)) => region.to_uppercase(),
+        None => LOCALE_MARKETS.iter().find(|(v, _)| *v == locale)?.1.to_owned(),
+    };
+
+    if MARKETS.contains(&market.as_str()) {
+        Some(market)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_markets_accepts_codes_in_any_case_and_separator() {
+        assert_eq!(parse_markets("us, gb  SE,us").unwrap(), vec!["GB", "SE", "US"]);
+    }
+
+    #[test]
+    fn parse_markets_expands_presets() {
+        assert_eq!(parse_markets("dach").unwrap(), vec!["AT", "CH", "DE"]);
+        assert_eq!(parse_markets("BENELUX NL US").unwrap(), vec!["BE", "LU", "NL", "US"]);
+        assert_eq!(parse_markets("EU").unwrap().len(), 27);
+
+        for (name, preset) in PRESETS {
+            assert!(preset.iter().all(|v| MARKETS.contains(v)), "{} has an unknown market", name);
+        }
+    }
+
+    #[test]
+    fn parse_markets_names_invalid_tokens() {
+        let e = parse_markets("US XX").unwrap_err();
+        assert!(e.starts_with("`XX` is not a market"), "{}", e);
+
+        let e = parse_markets("XX, europe").unwrap_err();
+        assert!(e.starts_with("`XX`, `EUROPE` are not a market"), "{}", e);
+    }
+
+    #[test]
+    fn parse_markets_rejects_empty_input() {
+        assert!(parse_markets("").is_err());
+        assert!(parse_markets(" , ").is_err());
+    }
+
+    #[test]
+    fn describe_markets_collapses_presets() {
+        assert_eq!(describe_markets(&parse_markets("NORDICS").unwrap()), "NORDICS");
+        assert_eq!(describe_markets(&parse_markets("DK FI").unwrap()), "DK, FI");
+    }
+
+    #[test]
+    fn market_from_locale_uses_region_or_language() {
+        assert_eq!(market_from_locale("en-US").as_deref(), Some("US"));
+        assert_eq!(market_from_locale("pt-BR").as_deref(), Some("BR"));
+        assert_eq!(market_from_locale("ja").as_deref(), Some("JP"));
+        assert_eq!(market_from_locale("uk").as_deref(), Some("UA"));
+    }
+
+    #[test]
+    fn market_from_locale_skips_unsupported_countries() {
+        assert_eq!(market_from_locale("zh-CN"), None);
+        assert_eq!(market_from_locale("ru"), None);
+    }
+}
@@ -1,4 +1,24 @@
-use rspotify::{ClientCredsSpotify, Config, Credentials};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use rspotify::{ClientCredsSpotify, ClientError, Config, Credentials};
+use rspotify::clients::BaseClient;
+use rspotify::http::{HttpError, Query};
+use rspotify::model::{AlbumId, ArtistId, FullArtist, Id, Page, PlayableItem, PlaylistId, SearchResult, SearchType, SimplifiedAlbum, SimplifiedArtist, TrackId};
+
+pub mod markets;
+pub mod release_types;
+
+static MATCH_ARTIST_ID: Lazy<Regex> = Lazy::new(|| Regex::new(r#"^(?:https://open\.spotify\.com/(?:[\w-]+/)?artist/|spotify:artist:)?([0-9A-Za-z]{22})(?:[/?#].*)?$"#).unwrap());
+static MATCH_PLAYLIST_ID: Lazy<Regex> = Lazy::new(|| Regex::new(r#"^(?:https://open\.spotify\.com/(?:[\w-]+/)?playlist/|spotify:playlist:)?([0-9A-Za-z]{22})(?:[/?#].*)?$"#).unwrap());
+static MATCH_LINK: Lazy<Regex> = Lazy::new(|| Regex::new(r#"(?:https?://open\.spotify\.com/(?:[\w-]+/)?|spotify:)(artist|album|track)[/:]([0-9A-Za-z]{22})"#).unwrap());
+
+/// An artist, album or track link found in free text, by Spotify ID.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SpotifyLink {
+    Artist(String),
+    Album(String),
+    Track(String),
+}
 
 pub struct SpotifyClient {
     client: ClientCredsSpotify,
@@ -20,4 +40,279 @@ impl SpotifyClient {
     pub fn conn(&self) -> &ClientCredsSpotify {
         &self.client
     }
+
+    pub async fn artist(&self, id: &str) -> anyhow::Result<FullArtist> {
+        self.client.auto_reauth().await?;
+
+        let id = ArtistId::from_id(id)?;
+        Ok(self.client.artist(&id).await?)
+    }
+
+    /// Fetches a page of an artist's releases in any of `groups`, newest-first within each group.
+    /// `artist_albums_manual` can only request one group at a time.
+    pub async fn artist_albums(&self, id: &str, groups: &[&str], limit: u32, offset: u32) -> anyhow::Result<Page<SimplifiedAlbum>> {
+        self.client.auto_reauth().await?;
+
+        let id = ArtistId::from_id(id)?;
+        let include_groups = groups.join(",");
+        let limit = limit.to_string();
+        let offset = offset.to_string();
+
+        let mut params = Query::new();
+        params.insert("include_groups", &include_groups);
+        params.insert("limit", &limit);
+        params.insert("offset", &offset);
+
+        let result = self.client.endpoint_get(&format!("artists/{}/albums", id.id()), &params).await?;
+        Ok(serde_json::from_str(&result)?)
+    }
+
+    /// Fetches several artists at once; IDs that don't exist are left out of the result.
+    /// rspotify's `artists` can't be used, since Spotify returns `null` for each unknown ID and that
+    /// fails the whole request.
+    pub async fn artists(&self, ids: &[String]) -> anyhow::Result<Vec<FullArtist>> {
+        self.client.auto_reauth().await?;
+
+        let ids = ids.iter()
+            .map(|v| ArtistId::from_id(v).map(|v| v.id().to_owned()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut result = Vec::new();
+
+        // The several-artists endpoint accepts at most 50 IDs per request.
+        for chunk in ids.chunks(50) {
+            let ids = chunk.join(",");
+
+            let mut params = Query::new();
+            params.insert("ids", &ids);
+
+            let response = self.client.endpoint_get("artists", &params).await?;
+            let mut response: serde_json::Value = serde_json::from_str(&response)?;
+
+            if let Some(serde_json::Value::Array(artists)) = response.get_mut("artists").map(serde_json::Value::take) {
+                for artist in artists.into_iter().filter(|v| !v.is_null()) {
+                    result.push(serde_json::from_value(artist)?);
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Returns the distinct primary (first-credited) artists of a playlist's tracks, in playlist
+    /// order. Local files and podcast episodes are skipped.
+    pub async fn playlist_artists(&self, playlist_id: &str) -> anyhow::Result<Vec<SimplifiedArtist>> {
+        self.client.auto_reauth().await?;
+
+        let id = PlaylistId::from_id(playlist_id)?;
+
+        let mut result: Vec<SimplifiedArtist> = Vec::new();
+        let mut offset = 0;
+
+        loop {
+            let page = self.client.playlist_items_manual(&id, None, None, Some(100), Some(offset)).await?;
+            let count = page.items.len() as u32;
+
+            for item in page.items {
+                let track = match item.track {
+                    Some(PlayableItem::Track(v)) => v,
+                    _ => continue,
+                };
+
+                let artist = match track.artists.into_iter().next() {
+                    Some(v) => v,
+                    None => continue,
+                };
+
+                let artist_id = match &artist.id {
+                    Some(v) => v.id().to_owned(),
+                    None => continue,
+                };
+
+                if !result.iter().any(|v| v.id.as_ref().map_or(false, |id| id.id() == artist_id)) {
+                    result.push(artist);
+                }
+            }
+
+            if page.next.is_none() || count == 0 {
+                break;
+            }
+
+            offset += count;
+        }
+
+        Ok(result)
+    }
+
+    /// Resolves links to the artists they credit, in link order: artist links to that artist, and
+    /// album and track links to each of their artists. Links that don't resolve are skipped.
+    pub async fn link_artists(&self, links: &[SpotifyLink]) -> anyhow::Result<Vec<FullArtist>> {
+        self.client.auto_reauth().await?;
+
+        let mut ids: Vec<String> = Vec::new();
+
+        for link in links {
+            let credited = match link {
+                SpotifyLink::Artist(id) => vec![id.clone()],
+                SpotifyLink::Album(id) => match self.client.album(&AlbumId::from_id(id)?).await {
+                    Ok(v) => v.artists.into_iter().filter_map(|v| v.id).map(|v| v.id().to_owned()).collect(),
+                    Err(_) => continue,
+                },
+                SpotifyLink::Track(id) => match self.client.track(&TrackId::from_id(id)?).await {
+                    Ok(v) => v.artists.into_iter().filter_map(|v| v.id).map(|v| v.id().to_owned()).collect(),
+                    Err(_) => continue,
+                },
+            };
+
+            for id in credited {
+                if !ids.contains(&id) {
+                    ids.push(id);
+                }
+            }
+        }
+
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        self.artists(&ids).await
+    }
+
+    pub async fn search_artists(&self, query: &str, limit: u32) -> anyhow::Result<Vec<FullArtist>> {
+        self.client.auto_reauth().await?;
+
+        let result = self.client.search(query, &SearchType::Artist, None, None, Some(limit), None).await?;
+
+        match result {
+            SearchResult::Artists(page) => Ok(page.items),
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    /// Resolves user input to an artist. Accepts artist links, `spotify:artist:` URIs, and bare
+    /// artist IDs (which is what autocomplete choices submit); anything else is treated as a name
+    /// and resolved to the top search result.
+    ///
+    /// Returns `None` only when Spotify has no such artist; other failures are returned as errors.
+    pub async fn resolve_artist(&self, input: &str) -> anyhow::Result<Option<FullArtist>> {
+        let input = input.trim();
+        if input.is_empty() {
+            return Ok(None);
+        }
+
+        match parse_artist_id(input) {
+            Some(id) => {
+                self.client.auto_reauth().await?;
+
+                let id = ArtistId::from_id(&id)?;
+                match self.client.artist(&id).await {
+                    Ok(v) => Ok(Some(v)),
+                    Err(e) if is_not_found(&e) => Ok(None),
+                    Err(e) => Err(e.into()),
+                }
+            }
+            None => Ok(self.search_artists(input, 1).await?.into_iter().next()),
+        }
+    }
+}
+
+/// Returns whether Spotify rejected a request because the ID doesn't exist: 404 for unknown IDs,
+/// and 400 for IDs that are well-formed but invalid.
+fn is_not_found(e: &ClientError) -> bool {
+    match e {
+        ClientError::Http(e) => match e.as_ref() {
+            HttpError::StatusCode(r) => matches!(r.status().as_u16(), 400 | 404),
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+#[must_use]
+pub fn parse_artist_id(input: &str) -> Option<String> {
+    MATCH_ARTIST_ID.captures(input.trim())
+        .and_then(|v| v.get(1))
+        .map(|v| v.as_str().to_owned())
+}
+
+#[must_use]
+pub fn parse_playlist_id(input: &str) -> Option<String> {
+    MATCH_PLAYLIST_ID.captures(input.trim())
+        .and_then(|v| v.get(1))
+        .map(|v| v.as_str().to_owned())
+}
+
+/// Finds every distinct Spotify artist, album and track link or URI in `text`.
+#[must_use]
+pub fn find_links(text: &str) -> Vec<SpotifyLink> {
+    let mut result = Vec::new();
+
+    for captures in MATCH_LINK.captures_iter(text) {
+        let id = captures[2].to_owned();
+        let link = match &captures[1] {
+            "artist" => SpotifyLink::Artist(id),
+            "album" => SpotifyLink::Album(id),
+            _ => SpotifyLink::Track(id),
+        };
+
+        if !result.contains(&link) {
+            result.push(link);
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ID: &str = "4Z8W4fKeB5YxbusRsdQVPb";
+
+    #[test]
+    fn parse_artist_id_accepts_links_uris_and_ids() {
+        for input in [
+            format!("https://open.spotify.com/artist/{}", ID),
+            format!("https://open.spotify.com/intl-de/artist/{}", ID),
+            format!("https://open.spotify.com/artist/{}?si=abc123", ID),
+            format!("spotify:artist:{}", ID),
+            format!("  {}  ", ID),
+        ] {
+            assert_eq!(parse_artist_id(&input).as_deref(), Some(ID), "{}", input);
+        }
+    }
+
+    #[test]
+    fn parse_artist_id_rejects_other_input() {
+        for input in [
+            format!("https://open.spotify.com/album/{}", ID),
+            format!("spotify:track:{}", ID),
+            "Radiohead".to_owned(),
+            ID[1..].to_owned(),
+        ] {
+            assert_eq!(parse_artist_id(&input), None, "{}", input);
+        }
+    }
+
+    #[test]
+    fn find_links_finds_each_kind_once() {
+        let text = format!(
+            "new one https://open.spotify.com/intl-fr/album/{id}?si=x from spotify:artist:{id}, \
+             also https://open.spotify.com/track/{id} and https://open.spotify.com/artist/{id}",
+            id = ID,
+        );
+
+        assert_eq!(find_links(&text), vec![
+            SpotifyLink::Album(ID.to_owned()),
+            SpotifyLink::Artist(ID.to_owned()),
+            SpotifyLink::Track(ID.to_owned()),
+        ]);
+    }
+
+    #[test]
+    fn find_links_ignores_bare_ids_and_other_links() {
+        let text = format!("{} https://open.spotify.com/playlist/{} https://example.com/artist/{}", ID, ID, ID);
+
+        assert!(find_links(&text).is_empty());
+    }
 }
@@ -0,0 +1,101 @@
+/// Spotify album groups a watch can filter on, with their display names.
+pub const RELEASE_TYPES: &[(&str, &str)] = &[
+    ("album", "Album"),
+    ("single", "Single/EP"),
+    ("compilation", "Compilation"),
+    ("appears_on", "Appears on"),
+];
+
+pub const DEFAULT_RELEASE_TYPES: &[&str] = &["album", "single", "compilation"];
+
+#[must_use]
+pub fn default_release_types() -> Vec<String> {
+    DEFAULT_RELEASE_TYPES.iter().map(|v| v.to_string()).collect()
+}
+
+/// Parses a comma- or space-separated list of release types. Accepts the album group names and
+/// a few common spellings ("singles", "ep", "features", "appears-on"). On failure, returns a
+/// message for the user naming the invalid entries.
+pub fn parse_release_types(input: &str) -> Result<Vec<String>, String> {
+    let mut types = Vec::new();
+    let mut invalid = Vec::new();
+
+    for token in input.split(|c: char| c == ',' || c.is_whitespace()).filter(|v| !v.is_empty()) {
+        let token = token.to_lowercase();
+
+        let name = match token.as_str() {
+            "album" | "albums" => "album",
+            "single" | "singles" | "ep" | "eps" => "single",
+            "compilation" | "compilations" => "compilation",
+            "appears_on" | "appears-on" | "feature" | "features" => "appears_on",
+            _ => {
+                invalid.push(token);
+                continue;
+            }
+        };
+
+        types.push(name.to_owned());
+    }
+
+    if !invalid.is_empty() {
+        return Err(format!(
+            "{} {} not a release type; use any of: {}.",
+            invalid.iter().map(|v| format!("`{}`", v)).collect::<Vec<_>>().join(", "),
+            if invalid.len() == 1 { "is" } else { "are" },
+            RELEASE_TYPES.iter().map(|(name, _)| format!("`{}`", name)).collect::<Vec<_>>().join(", "),
+        ));
+    }
+
+    if types.is_empty() {
+        return Err("At least one release type is required, like `album` or `single`.".to_owned());
+    }
+
+    // Keep the canonical order so stored sets compare and display consistently.
+    Ok(RELEASE_TYPES.iter()
+        .map(|(name, _)| name.to_string())
+        .filter(|v| types.contains(v))
+        .collect())
+}
+
+#[must_use]
+pub fn display_name(release_type: &str) -> &str {
+    RELEASE_TYPES.iter()
+        .find(|(name, _)| *name == release_type)
+        .map_or(release_type, |(_, display)| display)
+}
+
+#[must_use]
+pub fn describe_release_types(types: &[String]) -> String {
+    types.iter().map(|v| display_name(v)).collect::<Vec<_>>().join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_release_types_accepts_aliases() {
+        assert_eq!(parse_release_types("Singles, EP features").unwrap(), vec!["single", "appears_on"]);
+        assert_eq!(parse_release_types("appears-on").unwrap(), vec!["appears_on"]);
+    }
+
+    #[test]
+    fn parse_release_types_keeps_canonical_order() {
+        assert_eq!(
+            parse_release_types("appears_on compilation album single album").unwrap(),
+            vec!["album", "single", "compilation", "appears_on"],
+        );
+    }
+
+    #[test]
+    fn parse_release_types_names_invalid_tokens() {
+        let e = parse_release_types("album mixtape").unwrap_err();
+        assert!(e.starts_with("`mixtape` is not a release type"), "{}", e);
+    }
+
+    #[test]
+    fn parse_release_types_rejects_empty_input() {
+        assert!(parse_release_types("").is_err());
+        assert!(parse_release_types(",").is_err());
+    }
+}